@@ -0,0 +1,272 @@
+use crate::eng::GroceryItem;
+use crate::quality::ProteinSource;
+use crate::quantity::Grams;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EssentialAminoAcid {
+    Histidine,
+    Isoleucine,
+    Leucine,
+    Lysine,
+    SulfurAminoAcids,   // methionine + cysteine
+    AromaticAminoAcids, // phenylalanine + tyrosine
+    Threonine,
+    Tryptophan,
+    Valine,
+}
+
+impl EssentialAminoAcid {
+    pub const ALL: [EssentialAminoAcid; 9] = [
+        EssentialAminoAcid::Histidine,
+        EssentialAminoAcid::Isoleucine,
+        EssentialAminoAcid::Leucine,
+        EssentialAminoAcid::Lysine,
+        EssentialAminoAcid::SulfurAminoAcids,
+        EssentialAminoAcid::AromaticAminoAcids,
+        EssentialAminoAcid::Threonine,
+        EssentialAminoAcid::Tryptophan,
+        EssentialAminoAcid::Valine,
+    ];
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EssentialAminoAcid::Histidine => "histidine",
+            EssentialAminoAcid::Isoleucine => "isoleucine",
+            EssentialAminoAcid::Leucine => "leucine",
+            EssentialAminoAcid::Lysine => "lysine",
+            EssentialAminoAcid::SulfurAminoAcids => "methionine + cysteine",
+            EssentialAminoAcid::AromaticAminoAcids => "phenylalanine + tyrosine",
+            EssentialAminoAcid::Threonine => "threonine",
+            EssentialAminoAcid::Tryptophan => "tryptophan",
+            EssentialAminoAcid::Valine => "valine",
+        }
+    }
+}
+
+impl Display for EssentialAminoAcid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.label())
+    }
+}
+
+/* Milligrams of each essential amino acid per gram of protein, in `EssentialAminoAcid::ALL` order. */
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AminoAcidProfile(pub [f64; 9]);
+
+impl AminoAcidProfile {
+    /* FAO 2013 scoring pattern for older children, adolescents and adults. */
+    pub const REFERENCE: AminoAcidProfile =
+        AminoAcidProfile([16.0, 30.0, 61.0, 48.0, 23.0, 41.0, 25.0, 6.6, 40.0]);
+
+    pub fn get(&self, amino_acid: EssentialAminoAcid) -> f64 {
+        self.0[amino_acid.index()]
+    }
+
+    pub fn set(&mut self, amino_acid: EssentialAminoAcid, mg_per_g: f64) {
+        self.0[amino_acid.index()] = mg_per_g;
+    }
+
+    pub fn ratio(&self, amino_acid: EssentialAminoAcid) -> f64 {
+        self.get(amino_acid) / AminoAcidProfile::REFERENCE.get(amino_acid)
+    }
+
+    pub fn limiting(&self) -> (EssentialAminoAcid, f64) {
+        EssentialAminoAcid::ALL
+            .into_iter()
+            .map(|amino_acid| (amino_acid, self.ratio(amino_acid)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap_or((EssentialAminoAcid::Histidine, 0.0))
+    }
+
+    pub fn for_source(source: ProteinSource) -> AminoAcidProfile {
+        /* Approximate reference values, mg per g protein. */
+        AminoAcidProfile(match source {
+            ProteinSource::Whey => [18.0, 62.0, 106.0, 94.0, 45.0, 62.0, 70.0, 18.0, 60.0],
+            ProteinSource::Casein => [28.0, 50.0, 92.0, 78.0, 33.0, 105.0, 42.0, 13.0, 64.0],
+            ProteinSource::Egg => [24.0, 54.0, 86.0, 70.0, 57.0, 93.0, 47.0, 17.0, 66.0],
+            ProteinSource::Milk => [27.0, 47.0, 95.0, 78.0, 33.0, 102.0, 44.0, 14.0, 64.0],
+            ProteinSource::Beef => [34.0, 45.0, 80.0, 84.0, 38.0, 75.0, 44.0, 11.0, 50.0],
+            ProteinSource::Chicken => [31.0, 53.0, 75.0, 85.0, 40.0, 73.0, 42.0, 12.0, 50.0],
+            ProteinSource::Soy => [26.0, 46.0, 78.0, 63.0, 26.0, 89.0, 38.0, 13.0, 48.0],
+            ProteinSource::Pea => [25.0, 45.0, 84.0, 72.0, 20.0, 91.0, 38.0, 10.0, 50.0],
+            ProteinSource::Chickpea => [28.0, 43.0, 71.0, 67.0, 27.0, 85.0, 37.0, 10.0, 42.0],
+            ProteinSource::BlackBean => [28.0, 44.0, 80.0, 69.0, 23.0, 82.0, 42.0, 12.0, 52.0],
+            ProteinSource::Lentil => [28.0, 43.0, 73.0, 70.0, 21.0, 80.0, 36.0, 9.0, 50.0],
+            ProteinSource::Rice => [23.0, 41.0, 82.0, 36.0, 47.0, 94.0, 36.0, 12.0, 60.0],
+            ProteinSource::Oat => [24.0, 41.0, 76.0, 42.0, 50.0, 85.0, 34.0, 14.0, 55.0],
+            ProteinSource::Wheat => [23.0, 36.0, 68.0, 27.0, 40.0, 78.0, 29.0, 12.0, 43.0],
+            ProteinSource::Peanut => [25.0, 35.0, 65.0, 36.0, 25.0, 92.0, 27.0, 10.0, 42.0],
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AminoError {
+    MissingProfile(String),
+    InvalidServings(String), // negative, NaN or infinite servings of the named item
+    NoProtein,
+}
+
+impl Display for AminoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AminoError::MissingProfile(name) => {
+                write!(f, "{} has no amino acid profile or protein source", name)
+            }
+            AminoError::InvalidServings(name) => {
+                write!(f, "Servings of {} must be a number, zero or more", name)
+            }
+            AminoError::NoProtein => write!(f, "The combination has no protein"),
+        }
+    }
+}
+
+impl std::error::Error for AminoError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CombinedAminoScore {
+    pub total_protein: Grams,
+    pub profile: AminoAcidProfile, // mg per g protein across the whole combination
+    pub score: f64,                // lowest ratio to the reference; 1.0 or more is complete
+    pub limiting: EssentialAminoAcid,
+    pub limiting_item: usize, // index of the portion lowest in the limiting amino acid, skipping empty ones
+}
+
+impl CombinedAminoScore {
+    pub fn is_complete(&self) -> bool {
+        self.score >= 1.0
+    }
+}
+
+impl GroceryItem {
+    pub fn amino_profile(&self) -> Option<AminoAcidProfile> {
+        /* An entered profile wins; otherwise fall back to the table for the protein source. */
+        self.amino_acids.or_else(|| {
+            self.quality
+                .and_then(|quality| quality.source)
+                .map(AminoAcidProfile::for_source)
+        })
+    }
+}
+
+pub fn combine(portions: &[(&GroceryItem, f64)]) -> Result<CombinedAminoScore, AminoError> {
+    /* Each portion is an item and how many of its servings go into the combination. */
+    let mut total_protein = 0.0;
+    let mut totals = [0.0; 9];
+    let mut profiles = vec![];
+    for (index, (item, servings)) in portions.iter().enumerate() {
+        if !servings.is_finite() || *servings < 0.0 {
+            return Err(AminoError::InvalidServings(item.name.clone()));
+        }
+        let profile = item
+            .amino_profile()
+            .ok_or_else(|| AminoError::MissingProfile(item.name.clone()))?;
+        let grams = item.protein.value() * servings;
+        total_protein += grams;
+        for (total, mg_per_g) in totals.iter_mut().zip(profile.0) {
+            *total += mg_per_g * grams;
+        }
+        /* A portion of nothing adds no amino acids, so it can't be what holds the rest back. */
+        if *servings > 0.0 {
+            profiles.push((index, profile));
+        }
+    }
+    if total_protein <= 0.0 {
+        return Err(AminoError::NoProtein);
+    }
+
+    let profile = AminoAcidProfile(totals.map(|total| total / total_protein));
+    let (limiting, score) = profile.limiting();
+    let limiting_item = profiles
+        .iter()
+        .min_by(|a, b| a.1.get(limiting).total_cmp(&b.1.get(limiting)))
+        .map(|(index, _)| *index)
+        .unwrap_or(0);
+
+    Ok(CombinedAminoScore {
+        total_protein: Grams::new(total_protein),
+        profile,
+        score,
+        limiting,
+        limiting_item,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eng::{ProteinQuality, QualityScale, test_item};
+
+    fn item(name: &str, source: ProteinSource) -> GroceryItem {
+        let mut item = test_item(name, 5.0, 200.0, 3.0);
+        item.quality = Some(ProteinQuality::from_source(source, QualityScale::Pdcaas));
+        item
+    }
+
+    #[test]
+    fn rice_and_beans_complement_each_other() {
+        let rice = item("Rice", ProteinSource::Rice);
+        let beans = item("Black beans", ProteinSource::BlackBean);
+
+        let alone = combine(&[(&rice, 1.0)]).unwrap();
+        assert!(!alone.is_complete());
+        assert_eq!(alone.limiting, EssentialAminoAcid::Lysine);
+
+        let together = combine(&[(&rice, 1.0), (&beans, 1.0)]).unwrap();
+        assert!(together.is_complete());
+        assert_eq!(together.total_protein, Grams::new(10.0));
+    }
+
+    #[test]
+    fn names_the_item_short_on_the_limiting_amino_acid() {
+        let rice = item("Rice", ProteinSource::Rice);
+        let beans = item("Black beans", ProteinSource::BlackBean);
+        let combined = combine(&[(&beans, 1.0), (&rice, 3.0)]).unwrap();
+        assert_eq!(combined.limiting, EssentialAminoAcid::Lysine);
+        assert_eq!(combined.limiting_item, 1);
+    }
+
+    #[test]
+    fn empty_portions_are_never_limiting() {
+        let rice = item("Rice", ProteinSource::Rice);
+        let beans = item("Black beans", ProteinSource::BlackBean);
+        let combined = combine(&[(&rice, 0.0), (&beans, 1.0)]).unwrap();
+        assert_eq!(combined.limiting_item, 1);
+    }
+
+    #[test]
+    fn rejects_bad_servings() {
+        let rice = item("Rice", ProteinSource::Rice);
+        for servings in [-1.0, f64::NAN, f64::INFINITY] {
+            assert_eq!(
+                combine(&[(&rice, servings)]),
+                Err(AminoError::InvalidServings("Rice".to_string()))
+            );
+        }
+        assert_eq!(combine(&[(&rice, 0.0)]), Err(AminoError::NoProtein));
+    }
+
+    #[test]
+    fn needs_a_profile() {
+        let mut plain = item("Mystery", ProteinSource::Rice);
+        plain.quality = None;
+        assert_eq!(
+            combine(&[(&plain, 1.0)]),
+            Err(AminoError::MissingProfile("Mystery".to_string()))
+        );
+    }
+
+    #[test]
+    fn an_entered_profile_wins_over_the_table() {
+        let mut rice = item("Rice", ProteinSource::Rice);
+        rice.amino_acids = Some(AminoAcidProfile::REFERENCE);
+        assert_eq!(rice.amino_profile(), Some(AminoAcidProfile::REFERENCE));
+        assert!(combine(&[(&rice, 1.0)]).unwrap().is_complete());
+    }
+}
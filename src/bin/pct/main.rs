@@ -0,0 +1,422 @@
+use clap::{Parser, ValueEnum};
+use protein_comparison_tool::csv_io::{self, CsvError, ImportOptions};
+use protein_comparison_tool::eng::{GroceryItem, Money, SortMode, SortOrder};
+use protein_comparison_tool::persist::PersistError;
+use protein_comparison_tool::session::Session;
+use std::fmt::Display;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+#[cfg(feature = "tui")]
+mod tui;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum InputFormat {
+    Auto, // by extension, or by the first character on stdin
+    Csv,
+    Json, // a session file, a bare array of items, or JSON Lines
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Table,
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "pct",
+    version,
+    about = "Rank protein sources from CSV or JSON files, best first"
+)]
+struct Args {
+    #[arg(help = "Files to read; none or '-' reads stdin")]
+    files: Vec<PathBuf>,
+    #[arg(long, value_enum, default_value_t = InputFormat::Auto, help = "How to read the input")]
+    input: InputFormat,
+    #[arg(long, short, default_value_t = SortMode::Ppd, help = "insertion, leanness, ppd, lpd, cost or name")]
+    sort: SortMode,
+    #[arg(
+        long,
+        conflicts_with = "asc",
+        help = "Highest first, whichever way is better"
+    )]
+    desc: bool,
+    #[arg(long, help = "Lowest first, whichever way is better")]
+    asc: bool,
+    #[arg(
+        long,
+        short = 'n',
+        value_name = "N",
+        help = "Only print the first N items"
+    )]
+    top: Option<usize>,
+    #[arg(long, help = "Only items whose name contains this (any case)")]
+    name: Option<String>,
+    #[arg(long, value_name = "G", help = "Grams of protein per serving")]
+    min_protein: Option<f64>,
+    #[arg(long, value_name = "KCAL", help = "kCal per serving")]
+    max_calories: Option<f64>,
+    #[arg(long, value_name = "PRICE", help = "Package price, e.g. 12.99")]
+    max_cost: Option<Money>,
+    #[arg(long, help = "kCal per gram of protein")]
+    max_leanness: Option<f64>,
+    #[arg(long, help = "Grams of protein per dollar")]
+    min_ppd: Option<f64>,
+    #[arg(long, help = "Leanness per dollar")]
+    max_lpd: Option<f64>,
+    #[arg(long, short, value_enum, default_value_t = OutputFormat::Table, help = "How to print the ranked items")]
+    output: OutputFormat,
+    #[cfg(feature = "tui")]
+    #[arg(
+        long,
+        help = "Edit the list interactively; a single .json file is opened and saved in place"
+    )]
+    tui: bool,
+}
+
+#[derive(Debug)]
+enum CliError {
+    Read(String, std::io::Error),
+    Csv(String, CsvError),
+    Json(String, PersistError),
+    Write(std::io::Error),
+    #[cfg(feature = "tui")]
+    Terminal(std::io::Error),
+}
+
+impl Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CliError::Read(source, err) => write!(f, "{}: {}", source, err),
+            CliError::Csv(source, err) => write!(f, "{}: {}", source, err),
+            CliError::Json(source, err) => write!(f, "{}: {}", source, err),
+            CliError::Write(err) => write!(f, "stdout: {}", err),
+            #[cfg(feature = "tui")]
+            CliError::Terminal(err) => write!(f, "terminal: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+fn read_source(path: Option<&Path>) -> Result<(String, String), CliError> {
+    /* The source's display name and its text. */
+    match path {
+        None => {
+            let text = std::io::read_to_string(std::io::stdin())
+                .map_err(|err| CliError::Read("stdin".to_string(), err))?;
+            Ok(("stdin".to_string(), text))
+        }
+        Some(path) => {
+            let name = path.display().to_string();
+            let text =
+                std::fs::read_to_string(path).map_err(|err| CliError::Read(name.clone(), err))?;
+            Ok((name, text))
+        }
+    }
+}
+
+fn detect_format(path: Option<&Path>, text: &str) -> InputFormat {
+    let extension = path
+        .and_then(Path::extension)
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+    match extension.as_deref() {
+        Some("csv") => InputFormat::Csv,
+        Some("json") | Some("jsonl") => InputFormat::Json,
+        _ if text.trim_start().starts_with(['{', '[']) => InputFormat::Json,
+        _ => InputFormat::Csv,
+    }
+}
+
+fn load(path: Option<&Path>, format: InputFormat) -> Result<Vec<GroceryItem>, CliError> {
+    let (source, text) = read_source(path)?;
+    let format = match format {
+        InputFormat::Auto => detect_format(path, &text),
+        format => format,
+    };
+    match format {
+        InputFormat::Json => Session::from_text(&text)
+            .map(|session| session.items)
+            .map_err(|err| CliError::Json(source, err)),
+        _ => {
+            let import = csv_io::import(&text, &ImportOptions::default())
+                .map_err(|err| CliError::Csv(source.clone(), err))?;
+            /* Skipped rows are worth knowing about but shouldn't sink a whole price sheet. */
+            for err in &import.errors {
+                eprintln!("pct: {}: {}", source, err);
+            }
+            Ok(import.items)
+        }
+    }
+}
+
+impl Args {
+    fn order(&self) -> SortOrder {
+        /* Best first unless asked otherwise. */
+        let mut order = self.sort.best_first();
+        if self.desc {
+            order.descending = true;
+        } else if self.asc {
+            order.descending = false;
+        }
+        order
+    }
+
+    fn keeps(&self, item: &GroceryItem) -> bool {
+        let at_least = |limit: Option<f64>, value: f64| limit.is_none_or(|limit| value >= limit);
+        let at_most = |limit: Option<f64>, value: f64| limit.is_none_or(|limit| value <= limit);
+        self.name
+            .as_ref()
+            .is_none_or(|name| item.name.to_lowercase().contains(&name.to_lowercase()))
+            && at_least(self.min_protein, item.protein.value())
+            && at_most(self.max_calories, item.calories.value())
+            && self.max_cost.is_none_or(|cost| item.cost <= cost)
+            && at_most(self.max_leanness, item.leanness.value())
+            && at_least(self.min_ppd, item.ppd.value())
+            && at_most(self.max_lpd, item.lpd.value())
+    }
+}
+
+fn table(items: &[GroceryItem]) -> String {
+    let headers = [
+        "#", "Name", "Protein", "kCal", "Cost", "Servings", "Leanness", "PPD", "LPD",
+    ];
+    let rows: Vec<[String; 9]> = items
+        .iter()
+        .enumerate()
+        .map(|(rank, item)| {
+            [
+                (rank + 1).to_string(),
+                item.name.clone(),
+                format!("{:.1}", item.protein.value()),
+                format!("{:.0}", item.calories.value()),
+                format!("${}", item.cost),
+                format!("{}", item.servings.value()),
+                format!("{:.2}", item.leanness.value()),
+                format!("{:.2}", item.ppd.value()),
+                format!("{:.2}", item.lpd.value()),
+            ]
+        })
+        .collect();
+    let mut widths = headers.map(|header| header.chars().count());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let line = |cells: Vec<&str>| {
+        /* Names read left to right; numbers line up on the right. */
+        cells
+            .iter()
+            .zip(widths)
+            .enumerate()
+            .map(|(column, (cell, width))| {
+                if column == 1 {
+                    format!("{:<width$}", cell)
+                } else {
+                    format!("{:>width$}", cell)
+                }
+            })
+            .collect::<Vec<String>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+    let mut out = vec![line(headers.to_vec())];
+    out.extend(
+        rows.iter()
+            .map(|row| line(row.iter().map(String::as_str).collect())),
+    );
+    out.join("\n") + "\n"
+}
+
+fn load_all(args: &Args) -> Result<Vec<GroceryItem>, CliError> {
+    let mut items = Vec::new();
+    if args.files.is_empty() {
+        items.extend(load(None, args.input)?);
+    }
+    for path in &args.files {
+        let path = (path.as_os_str() != "-").then_some(path.as_path());
+        items.extend(load(path, args.input)?);
+    }
+    Ok(items)
+}
+
+#[cfg(feature = "tui")]
+fn run_tui(args: &Args) -> Result<(), CliError> {
+    /*
+    One .json file is the session to edit, and may not exist yet. Anything else is read as
+    usual into a new, unsaved list; with no files the list starts empty rather than reading stdin.
+    */
+    let (session, path) = match args.files.as_slice() {
+        [] => (Session::default(), None),
+        [path]
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "json") =>
+        {
+            let session = if path.exists() {
+                let (source, text) = read_source(Some(path))?;
+                Session::from_text(&text).map_err(|err| CliError::Json(source, err))?
+            } else {
+                Session::default()
+            };
+            (session, Some(path.clone()))
+        }
+        _ => (Session::from_items(load_all(args)?), None),
+    };
+    tui::run(session, path).map_err(CliError::Terminal)
+}
+
+fn run(args: &Args) -> Result<(), CliError> {
+    #[cfg(feature = "tui")]
+    if args.tui {
+        return run_tui(args);
+    }
+
+    let mut items = load_all(args)?;
+    items.retain(|item| args.keeps(item));
+    let order = args.order();
+    let ranked: Vec<GroceryItem> = order
+        .sort(&items)
+        .into_iter()
+        .take(args.top.unwrap_or(usize::MAX))
+        .map(|index| items[index].clone())
+        .collect();
+
+    let text = match args.output {
+        OutputFormat::Table => table(&ranked),
+        OutputFormat::Csv => csv_io::export(&ranked),
+        OutputFormat::Json => {
+            /* A session the web app and `Session::from_json` open as-is, in ranked order. */
+            let session = Session {
+                sort: order,
+                ..Session::from_items(ranked)
+            };
+            session.to_json() + "\n"
+        }
+    };
+    /* One locked write, so errors come back here instead of panicking inside `print!`. */
+    let mut stdout = std::io::stdout().lock();
+    write!(stdout, "{}", text)
+        .and_then(|_| stdout.flush())
+        .map_err(CliError::Write)
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        /* `pct ... | head` closing the pipe early is the reader being done, not a failure. */
+        Err(CliError::Write(err)) if err.kind() == ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("pct: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn csv(rows: &str) -> Vec<GroceryItem> {
+        /* Built the way `load` builds them, from a price sheet with four servings a row. */
+        let text = format!("name,protein,calories,cost,servings\n{}", rows);
+        let import = csv_io::import(&text, &ImportOptions::default()).unwrap();
+        assert_eq!(import.errors, vec![]);
+        import.items
+    }
+
+    fn args(flags: &[&str]) -> Args {
+        Args::parse_from(std::iter::once("pct").chain(flags.iter().copied()))
+    }
+
+    #[test]
+    fn detects_the_format_by_extension() {
+        let json = "[]";
+        assert_eq!(
+            detect_format(Some(Path::new("prices.CSV")), json),
+            InputFormat::Csv
+        );
+        assert_eq!(
+            detect_format(Some(Path::new("week.json")), "name"),
+            InputFormat::Json
+        );
+        assert_eq!(
+            detect_format(Some(Path::new("week.jsonl")), "name"),
+            InputFormat::Json
+        );
+    }
+
+    #[test]
+    fn detects_the_format_by_the_first_character() {
+        assert_eq!(detect_format(None, "  {\"version\": 1}"), InputFormat::Json);
+        assert_eq!(detect_format(None, "\n[]"), InputFormat::Json);
+        assert_eq!(detect_format(None, "name,protein\n"), InputFormat::Csv);
+        assert_eq!(detect_format(None, ""), InputFormat::Csv);
+        /* An unknown extension falls back to looking at the text. */
+        assert_eq!(
+            detect_format(Some(Path::new("prices.txt")), "[]"),
+            InputFormat::Json
+        );
+    }
+
+    #[test]
+    fn keeps_everything_without_filters() {
+        assert!(args(&[]).keeps(&csv("Chicken,30,150,10,4")[0]));
+    }
+
+    #[test]
+    fn filters_by_name_in_any_case() {
+        let args = args(&["--name", "CHICK"]);
+        let items = csv("Chicken thighs,30,150,10,4\nTofu,30,150,10,4");
+        assert!(args.keeps(&items[0]));
+        assert!(!args.keeps(&items[1]));
+    }
+
+    #[test]
+    fn filters_by_each_bound() {
+        /* 20 g protein, 100 kCal, $5 for 4 servings: leanness 5, ppd 16, lpd 4. */
+        let base = &csv("Base,20,100,5,4")[0];
+        let cases: [(&[&str], &[&str]); 6] = [
+            (&["--min-protein", "20"], &["--min-protein", "20.1"]),
+            (&["--max-calories", "100"], &["--max-calories", "99"]),
+            (&["--max-cost", "5.00"], &["--max-cost", "4.99"]),
+            (&["--max-leanness", "5"], &["--max-leanness", "4.9"]),
+            (&["--min-ppd", "16"], &["--min-ppd", "16.1"]),
+            (&["--max-lpd", "4"], &["--max-lpd", "3.9"]),
+        ];
+        for (keeps, drops) in cases {
+            assert!(args(keeps).keeps(base), "{:?}", keeps);
+            assert!(!args(drops).keeps(base), "{:?}", drops);
+        }
+    }
+
+    #[test]
+    fn orders_best_first_unless_told() {
+        assert!(args(&["--sort", "ppd"]).order().descending);
+        assert!(!args(&["--sort", "ppd", "--asc"]).order().descending);
+        assert!(!args(&["--sort", "leanness"]).order().descending);
+        assert!(args(&["--sort", "leanness", "--desc"]).order().descending);
+    }
+
+    #[test]
+    fn table_columns_line_up() {
+        let out = table(&csv("Chicken breast,31,165,12.99,4\nEggs,6,70,4.49,4"));
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("#  Name            Protein"));
+        assert!(lines[1].starts_with("1  Chicken breast     31.0"));
+        assert!(lines[2].starts_with("2  Eggs                6.0"));
+        /* Right-aligned numbers end under the end of their header on every row. */
+        let protein_end = lines[0].find("Protein").unwrap() + "Protein".len();
+        for line in &lines[1..] {
+            assert_eq!(&line[protein_end - 2..protein_end], ".0");
+        }
+        assert!(lines.iter().all(|line| line.len() == lines[0].len()));
+    }
+}
@@ -0,0 +1,545 @@
+use protein_comparison_tool::eng::{
+    Grams, GroceryItem, Kcal, Money, Servings, SortMode, SortOrder,
+};
+use protein_comparison_tool::list::{Command, ItemId, ItemList};
+use protein_comparison_tool::session::Session;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+use std::path::PathBuf;
+
+/* The same five inputs as the Add form on the web page. */
+const FIELDS: [&str; 5] = [
+    "Name",
+    "Protein (g)",
+    "Calories (kCal)",
+    "Cost ($)",
+    "Servings",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Prompt {
+    Save,
+    Open,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Focus {
+    List,
+    Form(usize), // index into FIELDS
+    Prompt(Prompt),
+}
+
+pub struct App {
+    list: ItemList,
+    session: Session, // weights and notes ride along untouched so saving loses nothing
+    sort: SortOrder,
+    selected: usize, // row in display order
+    form: [String; 5],
+    editing: Option<ItemId>,
+    focus: Focus,
+    path_input: String,
+    path: Option<PathBuf>,
+    dirty: bool,
+    quit_warned: bool, // the last key was a q or Ctrl-C that got the unsaved-changes warning
+    status: String,
+    quit: bool,
+}
+
+impl App {
+    pub fn new(session: Session, path: Option<PathBuf>) -> Self {
+        App {
+            list: ItemList::from_items(session.items.clone()),
+            sort: session.sort,
+            session,
+            selected: 0,
+            form: Default::default(),
+            editing: None,
+            focus: Focus::List,
+            path_input: String::new(),
+            path,
+            dirty: false,
+            quit_warned: false,
+            status: "Press a to add an item, ? for keys.".to_string(),
+            quit: false,
+        }
+    }
+
+    fn order(&self) -> Vec<usize> {
+        self.sort.sort(&self.list.items())
+    }
+
+    fn selected_id(&self) -> Option<ItemId> {
+        let index = *self.order().get(self.selected)?;
+        self.list.entries().get(index).map(|entry| entry.id)
+    }
+
+    fn select_id(&mut self, id: ItemId) {
+        let ids = self.list.ids();
+        if let Some(row) = self.order().iter().position(|index| ids[*index] == id) {
+            self.selected = row;
+        }
+    }
+
+    fn clamp_selection(&mut self) {
+        self.selected = self.selected.min(self.list.len().saturating_sub(1));
+    }
+
+    fn apply(&mut self, command: Command) {
+        match self.list.apply(command) {
+            Ok(_) => self.dirty = true,
+            Err(err) => self.status = err.to_string(),
+        }
+        self.clamp_selection();
+    }
+
+    fn start_form(&mut self, editing: Option<ItemId>) {
+        self.form = match editing.and_then(|id| self.list.get(id)) {
+            Some(item) => [
+                item.name.clone(),
+                item.protein.value().to_string(),
+                item.calories.value().to_string(),
+                item.cost.to_string(),
+                item.servings.value().to_string(),
+            ],
+            None => Default::default(),
+        };
+        self.editing = editing;
+        self.focus = Focus::Form(0);
+    }
+
+    fn submit_form(&mut self) {
+        let number = |field: usize| {
+            self.form[field]
+                .trim()
+                .parse::<f64>()
+                .map_err(|_| format!("{} must be a number", FIELDS[field]))
+        };
+        let parsed = (|| -> Result<GroceryItem, String> {
+            let cost: Money = self.form[3]
+                .parse()
+                .map_err(|err| format!("{}: {}", FIELDS[3], err))?;
+            let mut builder = GroceryItem::builder()
+                .name(self.form[0].clone())
+                .protein(Grams::new(number(1)?))
+                .calories(Kcal::new(number(2)?))
+                .cost(cost)
+                .servings(Servings::new(number(4)?));
+            /* Editing here only touches the five basics; keep whatever else the item had. */
+            if let Some(original) = self.editing.and_then(|id| self.list.get(id)) {
+                builder = builder
+                    .serving_grams(original.serving_grams)
+                    .macros(original.macros)
+                    .quality(original.quality)
+                    .amino_acids(original.amino_acids);
+            }
+            builder.build().map_err(|issues| {
+                issues
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<String>>()
+                    .join("; ")
+            })
+        })();
+        let item = match parsed {
+            Ok(item) => item,
+            Err(message) => {
+                self.status = message;
+                return;
+            }
+        };
+        let name = item.name.clone();
+        let (command, done) = match self.editing.filter(|id| self.list.get(*id).is_some()) {
+            Some(id) => (Command::Edit(id, item), "Saved"),
+            /* Also covers an item undone away mid-edit: keep the user's work, as the web page does. */
+            None => (Command::Add(item), "Added"),
+        };
+        match self.list.apply(command) {
+            Ok(id) => {
+                self.dirty = true;
+                self.select_id(id);
+                self.status = format!("{} {}.", done, name);
+                self.close_form();
+            }
+            /* Leave the form open so nothing typed is lost. */
+            Err(err) => self.status = err.to_string(),
+        }
+    }
+
+    fn close_form(&mut self) {
+        self.form = Default::default();
+        self.editing = None;
+        self.focus = Focus::List;
+    }
+
+    fn save(&mut self, path: PathBuf) {
+        let session = Session {
+            items: self.list.items(),
+            sort: self.sort,
+            ..self.session.clone()
+        };
+        match std::fs::write(&path, session.to_json()) {
+            Ok(()) => {
+                self.status = format!("Saved {} items to {}.", self.list.len(), path.display());
+                self.session = session;
+                self.path = Some(path);
+                self.dirty = false;
+            }
+            Err(err) => self.status = format!("Couldn't save {}: {}", path.display(), err),
+        }
+    }
+
+    fn open(&mut self, path: PathBuf) {
+        let loaded = std::fs::read_to_string(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|text| Session::from_text(&text).map_err(|err| err.to_string()));
+        match loaded {
+            Ok(session) => {
+                /* Undo brings the previous list back, like opening a saved list on the web page. */
+                self.apply(Command::Load(session.items.clone()));
+                self.sort = session.sort;
+                self.session = session;
+                self.selected = 0;
+                self.status = format!("Opened {} ({} items).", path.display(), self.list.len());
+                self.path = Some(path);
+                self.dirty = false;
+            }
+            Err(err) => self.status = format!("Couldn't open {}: {}", path.display(), err),
+        }
+    }
+
+    fn prompt(&mut self, prompt: Prompt) {
+        self.path_input = self
+            .path
+            .as_ref()
+            .map(|path| path.display().to_string())
+            .unwrap_or_default();
+        self.focus = Focus::Prompt(prompt);
+    }
+
+    fn cycle_sort(&mut self, step: isize) {
+        /* Every mode opens best first, the same as the sort buttons on the web page. */
+        let modes = SortMode::ALL;
+        let current = modes
+            .iter()
+            .position(|mode| *mode == self.sort.mode)
+            .unwrap_or(0) as isize;
+        let next = (current + step).rem_euclid(modes.len() as isize) as usize;
+        self.sort = modes[next].best_first();
+        self.status = format!("Sorted by {}.", self.sort.describe());
+    }
+
+    fn move_selected(&mut self, step: isize) {
+        /* Moving only means something when the list shows the order items were added in. */
+        if self.sort != SortOrder::default() {
+            self.status = "Switch to Order Added (press s) to move items.".to_string();
+            return;
+        }
+        let Some(id) = self.selected_id() else {
+            return;
+        };
+        let Some(to) = self.selected.checked_add_signed(step) else {
+            return;
+        };
+        self.apply(Command::Move { id, to });
+        self.select_id(id);
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        if key.kind != KeyEventKind::Press {
+            return;
+        }
+        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+        if control && key.code == KeyCode::Char('c') {
+            /* Raw mode turns Ctrl-C into a key press; treat it as q from anywhere. */
+            let warned = std::mem::take(&mut self.quit_warned);
+            self.request_quit(warned);
+            return;
+        }
+        match self.focus {
+            Focus::List => self.handle_list_key(key.code, control),
+            Focus::Form(field) => match key.code {
+                KeyCode::Esc => self.close_form(),
+                KeyCode::Enter => self.submit_form(),
+                KeyCode::Tab | KeyCode::Down => {
+                    self.focus = Focus::Form((field + 1) % FIELDS.len());
+                }
+                KeyCode::BackTab | KeyCode::Up => {
+                    self.focus = Focus::Form((field + FIELDS.len() - 1) % FIELDS.len());
+                }
+                KeyCode::Backspace => {
+                    self.form[field].pop();
+                }
+                KeyCode::Char(c) if !control => self.form[field].push(c),
+                _ => {}
+            },
+            Focus::Prompt(prompt) => match key.code {
+                KeyCode::Esc => self.focus = Focus::List,
+                KeyCode::Enter => {
+                    self.focus = Focus::List;
+                    let input = self.path_input.trim();
+                    if input.is_empty() {
+                        return;
+                    }
+                    let path = PathBuf::from(input);
+                    match prompt {
+                        Prompt::Save => self.save(path),
+                        Prompt::Open => self.open(path),
+                    }
+                }
+                KeyCode::Backspace => {
+                    self.path_input.pop();
+                }
+                KeyCode::Char(c) if !control => self.path_input.push(c),
+                _ => {}
+            },
+        }
+    }
+
+    fn request_quit(&mut self, warned: bool) {
+        /*
+        One warning, then a second press really quits. Only saving clears `dirty`; the sort
+        is saved along with the items but changing it alone doesn't count as unsaved work.
+        */
+        if self.dirty && !warned {
+            self.quit_warned = true;
+            self.status = "Unsaved changes; press w to save or q again to quit.".to_string();
+        } else {
+            self.quit = true;
+        }
+    }
+
+    fn handle_list_key(&mut self, code: KeyCode, control: bool) {
+        /* Any other key in between means the next q warns again. */
+        let warned = std::mem::take(&mut self.quit_warned);
+        match code {
+            KeyCode::Char('q') => self.request_quit(warned),
+            KeyCode::Char('s') if control => match self.path.clone() {
+                Some(path) => self.save(path),
+                None => self.prompt(Prompt::Save),
+            },
+            KeyCode::Char('r') if control => {
+                if self.list.redo() {
+                    self.dirty = true;
+                }
+                self.clamp_selection();
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(self.list.len().saturating_sub(1));
+            }
+            KeyCode::Home | KeyCode::Char('g') => self.selected = 0,
+            KeyCode::End | KeyCode::Char('G') => self.selected = self.list.len().saturating_sub(1),
+            KeyCode::Char('K') => self.move_selected(-1),
+            KeyCode::Char('J') => self.move_selected(1),
+            KeyCode::Char('a') => self.start_form(None),
+            KeyCode::Char('e') | KeyCode::Enter => {
+                if let Some(id) = self.selected_id() {
+                    self.start_form(Some(id));
+                }
+            }
+            KeyCode::Char('d') | KeyCode::Delete => {
+                if let Some(id) = self.selected_id() {
+                    self.apply(Command::Remove(id));
+                    self.status = "Deleted; press u to undo.".to_string();
+                }
+            }
+            KeyCode::Char('s') => self.cycle_sort(1),
+            KeyCode::Char('S') => self.cycle_sort(-1),
+            KeyCode::Char('r') => {
+                self.sort.descending = !self.sort.descending;
+                self.status = format!("Sorted by {}.", self.sort.describe());
+            }
+            KeyCode::Char('u') => {
+                if self.list.undo() {
+                    self.dirty = true;
+                }
+                self.clamp_selection();
+            }
+            KeyCode::Char('w') => match self.path.clone() {
+                Some(path) => self.save(path),
+                None => self.prompt(Prompt::Save),
+            },
+            KeyCode::Char('W') => self.prompt(Prompt::Save),
+            KeyCode::Char('o') => self.prompt(Prompt::Open),
+            KeyCode::Char('?') => {
+                self.status = "a add  e edit  d delete  s/S sort  r reverse  J/K move  u undo  ^r redo  w save  W save as  o open  q/^c quit".to_string();
+            }
+            _ => {}
+        }
+    }
+
+    pub fn draw(&self, frame: &mut Frame) {
+        let [form_area, list_area, status_area] = Layout::vertical([
+            Constraint::Length(FIELDS.len() as u16 + 2),
+            Constraint::Min(3),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let label_width = FIELDS.iter().map(|label| label.len()).max().unwrap_or(0);
+        let form_lines: Vec<Line> = FIELDS
+            .iter()
+            .zip(&self.form)
+            .enumerate()
+            .map(|(field, (label, value))| {
+                let line = Line::from(format!("{:>label_width$}: {}", label, value));
+                if self.focus == Focus::Form(field) {
+                    line.bold()
+                } else {
+                    line
+                }
+            })
+            .collect();
+        let form_title = match self.editing.and_then(|id| self.list.get(id)) {
+            Some(item) => format!(" Edit {} (Enter saves, Esc cancels) ", item.name),
+            None if matches!(self.focus, Focus::Form(_)) => {
+                " Add Item (Tab next field, Enter adds, Esc cancels) ".to_string()
+            }
+            None => " Add Item (press a) ".to_string(),
+        };
+        frame.render_widget(
+            Paragraph::new(form_lines).block(Block::bordered().title(form_title)),
+            form_area,
+        );
+        if let Focus::Form(field) = self.focus {
+            let x =
+                form_area.x + 1 + label_width as u16 + 2 + self.form[field].chars().count() as u16;
+            frame.set_cursor_position((x, form_area.y + 1 + field as u16));
+        }
+
+        let items = self.list.items();
+        let rows = self.order().into_iter().map(|index| {
+            let item = &items[index];
+            Row::new(vec![
+                item.name.clone(),
+                format!("{:.1}", item.protein.value()),
+                format!("{:.0}", item.calories.value()),
+                format!("${}", item.cost),
+                format!("{}", item.servings.value()),
+                format!("{:.2}", item.leanness.value()),
+                format!("{:.2}", item.ppd.value()),
+                format!("{:.2}", item.lpd.value()),
+            ])
+        });
+        let widths = [
+            Constraint::Fill(1),
+            Constraint::Length(8),
+            Constraint::Length(6),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(7),
+            Constraint::Length(6),
+        ];
+        let title = format!(" {} Items, {} ", self.list.len(), self.sort.describe());
+        let table = Table::new(rows, widths)
+            .header(
+                Row::new(vec![
+                    "Name", "Protein", "kCal", "Cost", "Servings", "Leanness", "PPD", "LPD",
+                ])
+                .add_modifier(Modifier::BOLD),
+            )
+            .block(Block::bordered().title(title))
+            .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        let mut state = TableState::default();
+        if self.focus == Focus::List && !self.list.is_empty() {
+            state.select(Some(self.selected));
+        }
+        frame.render_stateful_widget(table, list_area, &mut state);
+
+        let status = match self.focus {
+            Focus::Prompt(prompt) => {
+                let label = match prompt {
+                    Prompt::Save => "Save as JSON to",
+                    Prompt::Open => "Open JSON file",
+                };
+                let text = format!("{}: {}", label, self.path_input);
+                frame.set_cursor_position((
+                    status_area.x + text.chars().count() as u16,
+                    status_area.y,
+                ));
+                text
+            }
+            _ => self.status.clone(),
+        };
+        frame.render_widget(Paragraph::new(status), status_area);
+    }
+}
+
+pub fn run(session: Session, path: Option<PathBuf>) -> std::io::Result<()> {
+    let mut terminal = ratatui::try_init()?;
+    let result = event_loop(&mut terminal, App::new(session, path));
+    ratatui::restore();
+    result
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, mut app: App) -> std::io::Result<()> {
+    while !app.quit {
+        terminal.draw(|frame| app.draw(frame))?;
+        if let Event::Key(key) = event::read()? {
+            app.handle_key(key);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use protein_comparison_tool::csv_io::{self, ImportOptions};
+
+    fn app() -> App {
+        let text = "name,protein,calories,cost,servings\nChicken,31,165,12.99,8";
+        let import = csv_io::import(text, &ImportOptions::default()).unwrap();
+        App::new(Session::from_items(import.items), None)
+    }
+
+    fn press(app: &mut App, code: KeyCode) {
+        app.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+    }
+
+    fn ctrl_c(app: &mut App) {
+        app.handle_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL));
+    }
+
+    #[test]
+    fn ctrl_c_quits_a_clean_list() {
+        let mut app = app();
+        ctrl_c(&mut app);
+        assert!(app.quit);
+    }
+
+    #[test]
+    fn ctrl_c_warns_about_unsaved_changes_first() {
+        let mut app = app();
+        press(&mut app, KeyCode::Char('d'));
+        ctrl_c(&mut app);
+        assert!(!app.quit);
+        assert!(app.status.starts_with("Unsaved changes"));
+        ctrl_c(&mut app);
+        assert!(app.quit);
+    }
+
+    #[test]
+    fn ctrl_c_quits_from_the_form() {
+        let mut app = app();
+        press(&mut app, KeyCode::Char('a'));
+        press(&mut app, KeyCode::Char('x'));
+        ctrl_c(&mut app);
+        assert!(app.quit);
+    }
+
+    #[test]
+    fn sorting_leaves_nothing_to_save() {
+        let mut app = app();
+        press(&mut app, KeyCode::Char('s'));
+        press(&mut app, KeyCode::Char('r'));
+        assert!(!app.dirty);
+        press(&mut app, KeyCode::Char('q'));
+        assert!(app.quit);
+    }
+}
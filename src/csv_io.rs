@@ -0,0 +1,482 @@
+use crate::eng::{
+    GroceryItem, Macros, Money, ProteinQuality, ProteinSource, QualityScale, ValidationIssue,
+};
+use crate::quantity::{Grams, Kcal, Servings};
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CsvColumn {
+    Name,
+    Protein,
+    Calories,
+    Cost,
+    Servings,
+    ServingGrams,
+    Fat,
+    Carbs,
+    Fiber,
+    Sugars,
+    AddedSugars,
+    SugarAlcohols,
+    QualityScale,
+    QualityScore,
+    QualitySource,
+    Leanness, // computed: written on export, ignored on import
+    Ppd,      // computed
+    Lpd,      // computed
+}
+
+impl CsvColumn {
+    pub const ALL: [CsvColumn; 18] = [
+        CsvColumn::Name,
+        CsvColumn::Protein,
+        CsvColumn::Calories,
+        CsvColumn::Cost,
+        CsvColumn::Servings,
+        CsvColumn::ServingGrams,
+        CsvColumn::Fat,
+        CsvColumn::Carbs,
+        CsvColumn::Fiber,
+        CsvColumn::Sugars,
+        CsvColumn::AddedSugars,
+        CsvColumn::SugarAlcohols,
+        CsvColumn::QualityScale,
+        CsvColumn::QualityScore,
+        CsvColumn::QualitySource,
+        CsvColumn::Leanness,
+        CsvColumn::Ppd,
+        CsvColumn::Lpd,
+    ];
+
+    /* Used for headerless files. */
+    pub const DEFAULT_ORDER: [CsvColumn; 5] = [
+        CsvColumn::Name,
+        CsvColumn::Protein,
+        CsvColumn::Calories,
+        CsvColumn::Cost,
+        CsvColumn::Servings,
+    ];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            CsvColumn::Name => "name",
+            CsvColumn::Protein => "protein_g",
+            CsvColumn::Calories => "calories_kcal",
+            CsvColumn::Cost => "cost",
+            CsvColumn::Servings => "servings",
+            CsvColumn::ServingGrams => "serving_g",
+            CsvColumn::Fat => "fat_g",
+            CsvColumn::Carbs => "carbs_g",
+            CsvColumn::Fiber => "fiber_g",
+            CsvColumn::Sugars => "sugars_g",
+            CsvColumn::AddedSugars => "added_sugars_g",
+            CsvColumn::SugarAlcohols => "sugar_alcohols_g",
+            CsvColumn::QualityScale => "quality_scale",
+            CsvColumn::QualityScore => "quality_score",
+            CsvColumn::QualitySource => "protein_source",
+            CsvColumn::Leanness => "leanness",
+            CsvColumn::Ppd => "ppd",
+            CsvColumn::Lpd => "lpd",
+        }
+    }
+
+    pub fn is_computed(&self) -> bool {
+        matches!(self, CsvColumn::Leanness | CsvColumn::Ppd | CsvColumn::Lpd)
+    }
+
+    pub fn from_header(header: &str) -> Option<CsvColumn> {
+        /* Forgiving about case, spacing, punctuation and units, since headers come from people. */
+        let wanted: String = header
+            .to_lowercase()
+            .chars()
+            .filter(|c| c.is_alphanumeric())
+            .collect();
+        let column = match wanted.as_str() {
+            "name" | "item" | "label" | "product" | "food" => CsvColumn::Name,
+            "protein" | "proteing" | "proteingrams" => CsvColumn::Protein,
+            "calories" | "caloriescal" | "calorieskcal" | "kcal" | "cal" | "energy" => {
+                CsvColumn::Calories
+            }
+            "cost" | "price" | "costusd" | "priceusd" => CsvColumn::Cost,
+            "servings" | "servingsperpackage" | "servingspercontainer" => CsvColumn::Servings,
+            "servingg" | "servinggrams" | "servingsize" | "servingsizeg" => CsvColumn::ServingGrams,
+            "fat" | "fatg" | "totalfat" | "totalfatg" => CsvColumn::Fat,
+            "carbs" | "carbsg" | "totalcarbs" | "carbohydrates" | "totalcarbohydrate" => {
+                CsvColumn::Carbs
+            }
+            "fiber" | "fiberg" | "fibre" | "dietaryfiber" => CsvColumn::Fiber,
+            "sugars" | "sugarsg" | "sugar" | "totalsugars" => CsvColumn::Sugars,
+            "addedsugars" | "addedsugarsg" => CsvColumn::AddedSugars,
+            "sugaralcohols" | "sugaralcoholsg" | "polyols" => CsvColumn::SugarAlcohols,
+            "qualityscale" | "scale" => CsvColumn::QualityScale,
+            "qualityscore" | "pdcaas" | "diaas" | "quality" => CsvColumn::QualityScore,
+            "proteinsource" | "source" => CsvColumn::QualitySource,
+            "leanness" => CsvColumn::Leanness,
+            "ppd" | "proteinperdollar" => CsvColumn::Ppd,
+            "lpd" | "leannessperdollar" => CsvColumn::Lpd,
+            _ => return None,
+        };
+        Some(column)
+    }
+}
+
+impl Display for CsvColumn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.key())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CsvError {
+    Read(String),
+    /* Carries what was found so a UI can still offer the mapping for the user to fix. */
+    NoNameColumn {
+        headers: Option<Vec<String>>,
+        mapping: Vec<Option<CsvColumn>>,
+    },
+}
+
+impl Display for CsvError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CsvError::Read(message) => write!(f, "Couldn't read the CSV: {}", message),
+            CsvError::NoNameColumn { .. } => write!(f, "No column is mapped to the item name"),
+        }
+    }
+}
+
+impl std::error::Error for CsvError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowError {
+    pub line: u64, // 1-based, as a spreadsheet would number it
+    pub message: String,
+}
+
+impl Display for RowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Line {}: {}", self.line, self.message)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImportOptions {
+    pub mapping: Option<Vec<Option<CsvColumn>>>, // per CSV column; None skips it
+    pub has_header: Option<bool>,                // None to detect
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Import {
+    pub items: Vec<GroceryItem>,
+    pub errors: Vec<RowError>,
+    pub mapping: Vec<Option<CsvColumn>>, // what was used, so a UI can show and adjust it
+    pub headers: Option<Vec<String>>,
+}
+
+fn looks_like_header(record: &csv::StringRecord) -> bool {
+    /* Known column names and nothing that reads as a number. */
+    record
+        .iter()
+        .any(|cell| CsvColumn::from_header(cell).is_some())
+        && record
+            .iter()
+            .all(|cell| cell.trim().parse::<f64>().is_err())
+}
+
+pub fn detect_mapping(headers: &[String]) -> Vec<Option<CsvColumn>> {
+    /* First match wins if two headers mean the same thing. */
+    let mut seen = vec![];
+    headers
+        .iter()
+        .map(|header| {
+            let column = CsvColumn::from_header(header).filter(|column| !seen.contains(column));
+            if let Some(column) = column {
+                seen.push(column);
+            }
+            column
+        })
+        .collect()
+}
+
+fn parse_number(column: CsvColumn, cell: &str) -> Result<Option<f64>, String> {
+    let cell = cell.trim();
+    if cell.is_empty() {
+        return Ok(None);
+    }
+    match cell.parse::<f64>() {
+        /* "NaN" and "inf" parse as floats but would poison every metric they touch. */
+        Ok(value) if value.is_finite() => Ok(Some(value)),
+        _ => Err(format!("{} '{}' is not a number", column, cell)),
+    }
+}
+
+fn parse_row(
+    record: &csv::StringRecord,
+    mapping: &[Option<CsvColumn>],
+) -> Result<GroceryItem, String> {
+    let cell = |wanted: CsvColumn| {
+        mapping
+            .iter()
+            .position(|column| *column == Some(wanted))
+            .and_then(|index| record.get(index))
+            .unwrap_or("")
+    };
+    let number = |wanted: CsvColumn| parse_number(wanted, cell(wanted));
+    let grams = |wanted: CsvColumn| number(wanted).map(|value| value.map(Grams::new));
+    let required = |wanted: CsvColumn| number(wanted)?.ok_or(format!("{} is missing", wanted));
+
+    let cost_cell = cell(CsvColumn::Cost).trim();
+    if cost_cell.is_empty() {
+        return Err(format!("{} is missing", CsvColumn::Cost));
+    }
+    let cost: Money = cost_cell
+        .parse()
+        .map_err(|err| format!("{} {}", CsvColumn::Cost, err))?;
+
+    let scale = match cell(CsvColumn::QualityScale).trim() {
+        "" => QualityScale::default(),
+        text => text.parse::<QualityScale>()?,
+    };
+    let source = match cell(CsvColumn::QualitySource).trim() {
+        "" => None,
+        text => Some(text.parse::<ProteinSource>()?),
+    };
+    let quality = match (number(CsvColumn::QualityScore)?, source) {
+        (Some(score), source) => Some(ProteinQuality {
+            scale,
+            score,
+            source,
+        }),
+        (None, Some(source)) => Some(ProteinQuality::from_source(source, scale)),
+        (None, None) => None,
+    };
+
+    GroceryItem::builder()
+        .name(cell(CsvColumn::Name))
+        .protein(Grams::new(required(CsvColumn::Protein)?))
+        .calories(Kcal::new(required(CsvColumn::Calories)?))
+        .cost(cost)
+        .servings(Servings::new(number(CsvColumn::Servings)?.unwrap_or(1.0)))
+        .serving_grams(grams(CsvColumn::ServingGrams)?)
+        .macros(Macros {
+            fat: grams(CsvColumn::Fat)?,
+            carbs: grams(CsvColumn::Carbs)?,
+            fiber: grams(CsvColumn::Fiber)?,
+            sugars: grams(CsvColumn::Sugars)?,
+            added_sugars: grams(CsvColumn::AddedSugars)?,
+            sugar_alcohols: grams(CsvColumn::SugarAlcohols)?,
+        })
+        .quality(quality)
+        .build()
+        .map_err(|issues| {
+            issues
+                .iter()
+                .map(ValidationIssue::to_string)
+                .collect::<Vec<String>>()
+                .join("; ")
+        })
+}
+
+pub fn import(text: &str, options: &ImportOptions) -> Result<Import, CsvError> {
+    /* Bad rows are reported with their line and skipped; only an unreadable file fails outright. */
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(text.as_bytes());
+    let mut records = reader.records().peekable();
+
+    let first = match records.peek() {
+        Some(Ok(record)) => Some(record.clone()),
+        Some(Err(err)) => return Err(CsvError::Read(err.to_string())),
+        None => None,
+    };
+    let has_header = options
+        .has_header
+        .unwrap_or_else(|| first.as_ref().is_some_and(looks_like_header));
+    let headers = if has_header {
+        records.next();
+        first.map(|record| record.iter().map(str::to_string).collect::<Vec<String>>())
+    } else {
+        None
+    };
+    let mapping = match (&options.mapping, &headers) {
+        (Some(mapping), _) => mapping.clone(),
+        (None, Some(headers)) => detect_mapping(headers),
+        (None, None) => CsvColumn::DEFAULT_ORDER.iter().copied().map(Some).collect(),
+    };
+    if !mapping.contains(&Some(CsvColumn::Name)) {
+        return Err(CsvError::NoNameColumn { headers, mapping });
+    }
+
+    let mut items = vec![];
+    let mut errors = vec![];
+    for (index, record) in records.enumerate() {
+        let fallback_line = index as u64 + 1 + has_header as u64;
+        match record {
+            Ok(record) => {
+                if record.iter().all(|cell| cell.is_empty()) {
+                    continue;
+                }
+                let line = record
+                    .position()
+                    .map_or(fallback_line, |position| position.line());
+                match parse_row(&record, &mapping) {
+                    Ok(item) => items.push(item),
+                    Err(message) => errors.push(RowError { line, message }),
+                }
+            }
+            Err(err) => errors.push(RowError {
+                line: err
+                    .position()
+                    .map_or(fallback_line, |position| position.line()),
+                message: err.to_string(),
+            }),
+        }
+    }
+
+    Ok(Import {
+        items,
+        errors,
+        mapping,
+        headers,
+    })
+}
+
+fn optional(value: Option<f64>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+pub fn export(items: &[GroceryItem]) -> String {
+    /* Every column, with a header row, so the file round-trips through `import`. */
+    let mut writer = csv::Writer::from_writer(vec![]);
+    _ = writer.write_record(CsvColumn::ALL.iter().map(CsvColumn::key));
+    for item in items {
+        let grams = |value: Option<Grams>| optional(value.map(|grams| grams.value()));
+        let row: Vec<String> = CsvColumn::ALL
+            .iter()
+            .map(|column| match column {
+                CsvColumn::Name => item.name.clone(),
+                CsvColumn::Protein => item.protein.to_string(),
+                CsvColumn::Calories => item.calories.to_string(),
+                CsvColumn::Cost => item.cost.to_string(),
+                CsvColumn::Servings => item.servings.to_string(),
+                CsvColumn::ServingGrams => grams(item.serving_grams),
+                CsvColumn::Fat => grams(item.macros.fat),
+                CsvColumn::Carbs => grams(item.macros.carbs),
+                CsvColumn::Fiber => grams(item.macros.fiber),
+                CsvColumn::Sugars => grams(item.macros.sugars),
+                CsvColumn::AddedSugars => grams(item.macros.added_sugars),
+                CsvColumn::SugarAlcohols => grams(item.macros.sugar_alcohols),
+                CsvColumn::QualityScale => item
+                    .quality
+                    .map(|quality| quality.scale.to_string())
+                    .unwrap_or_default(),
+                CsvColumn::QualityScore => optional(item.quality.map(|quality| quality.score)),
+                CsvColumn::QualitySource => item
+                    .quality
+                    .and_then(|quality| quality.source)
+                    .map(|source| source.label().to_string())
+                    .unwrap_or_default(),
+                CsvColumn::Leanness => format!("{:.4}", item.leanness),
+                CsvColumn::Ppd => format!("{:.4}", item.ppd),
+                CsvColumn::Lpd => format!("{:.4}", item.lpd),
+            })
+            .collect();
+        _ = writer.write_record(&row);
+    }
+    writer
+        .into_inner()
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHEET: &str = "\
+name,protein,calories,cost,servings
+Chicken,31,165,12.99,8
+Eggs,6,70,4.49,12
+";
+
+    #[test]
+    fn imports_with_a_detected_header() {
+        let import = import(SHEET, &ImportOptions::default()).unwrap();
+        assert!(import.errors.is_empty());
+        assert_eq!(import.headers.as_ref().map(Vec::len), Some(5));
+        let names: Vec<&str> = import.items.iter().map(|item| item.name.as_str()).collect();
+        assert_eq!(names, ["Chicken", "Eggs"]);
+        assert_eq!(import.items[0].cost, Money::new(12.99));
+    }
+
+    #[test]
+    fn reports_malformed_rows_and_keeps_the_rest() {
+        let text = "\
+name,protein,calories,cost,servings
+Chicken,31,165,12.99,8
+Eggs,six,70,4.49,12
+Tofu,NaN,80,2.99,5
+Beans,7,110,,4
+Lentils,9,115,2.19,inf
+";
+        let import = import(text, &ImportOptions::default()).unwrap();
+        assert_eq!(import.items.len(), 1);
+        let lines: Vec<u64> = import.errors.iter().map(|err| err.line).collect();
+        assert_eq!(lines, [3, 4, 5, 6]);
+        assert_eq!(import.errors[0].message, "protein_g 'six' is not a number");
+        assert_eq!(import.errors[1].message, "protein_g 'NaN' is not a number");
+        assert_eq!(import.errors[2].message, "cost is missing");
+        assert_eq!(import.errors[3].message, "servings 'inf' is not a number");
+    }
+
+    #[test]
+    fn reports_missing_required_columns() {
+        let text = "name,protein,cost\nChicken,31,12.99\n";
+        let partial = import(text, &ImportOptions::default()).unwrap();
+        assert!(partial.items.is_empty());
+        assert_eq!(partial.errors[0].message, "calories_kcal is missing");
+
+        let text = "protein,calories,cost\n31,165,12.99\n";
+        assert!(matches!(
+            import(text, &ImportOptions::default()),
+            Err(CsvError::NoNameColumn { .. })
+        ));
+    }
+
+    #[test]
+    fn an_unrecognized_name_column_can_be_mapped() {
+        let text = "description,protein,calories,cost\nChicken,31,165,12.99\n";
+        let Err(CsvError::NoNameColumn {
+            headers,
+            mut mapping,
+        }) = import(text, &ImportOptions::default())
+        else {
+            panic!("expected the name column to be missing");
+        };
+        assert_eq!(
+            headers.as_deref().map(|headers| headers[0].as_str()),
+            Some("description")
+        );
+        assert_eq!(mapping[0], None);
+
+        mapping[0] = Some(CsvColumn::Name);
+        let options = ImportOptions {
+            mapping: Some(mapping),
+            has_header: None,
+        };
+        let import = import(text, &options).unwrap();
+        assert!(import.errors.is_empty());
+        assert_eq!(import.items[0].name, "Chicken");
+    }
+
+    #[test]
+    fn export_round_trips() {
+        let items = import(SHEET, &ImportOptions::default()).unwrap().items;
+        let again = import(&export(&items), &ImportOptions::default()).unwrap();
+        assert!(again.errors.is_empty());
+        assert_eq!(again.items, items);
+    }
+}
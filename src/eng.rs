@@ -1,43 +1,98 @@
-use core::panic;
-use ordered_float::OrderedFloat;
+use crate::amino::{AminoAcidProfile, EssentialAminoAcid};
+pub use crate::quality::{ProteinQuality, ProteinSource, QualityScale};
+pub use crate::quantity::{
+    Grams, GramsPerDollar, Kcal, KcalPerGram, LeannessPerDollar, Money, ParseMoneyError, Servings,
+};
+use crate::units::{Package, UnitError};
+use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 
 #[derive(Clone)]
-pub struct UxItem<T: Into<f64>, U: Into<f64>, V: Into<f64>, W: Into<f64>> {
+pub struct UxItem<T: Into<Grams>, U: Into<Kcal>, V: Into<Money>, W: Into<Servings>> {
     pub protein: T,
     pub calories: U,
     pub cost: V,
     pub servings: W,
     pub name: String,
+    pub serving_grams: Option<Grams>, // weight of one serving, if known
+    pub macros: Macros,
+    pub quality: Option<ProteinQuality>,
+    pub amino_acids: Option<AminoAcidProfile>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/* Optional per-serving label values beyond protein and calories. */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Macros {
+    pub fat: Option<Grams>,
+    pub carbs: Option<Grams>, // total carbohydrate
+    pub fiber: Option<Grams>,
+    pub sugars: Option<Grams>,
+    pub added_sugars: Option<Grams>,
+    pub sugar_alcohols: Option<Grams>,
+}
+
+impl Macros {
+    pub fn entries(&self) -> [(Field, Option<Grams>); 6] {
+        [
+            (Field::Fat, self.fat),
+            (Field::Carbs, self.carbs),
+            (Field::Fiber, self.fiber),
+            (Field::Sugars, self.sugars),
+            (Field::AddedSugars, self.added_sugars),
+            (Field::SugarAlcohols, self.sugar_alcohols),
+        ]
+    }
+
+    pub fn net_carbs(&self) -> Option<Grams> {
+        /* Fiber and sugar alcohols are mostly undigested; missing values count as zero. */
+        let zero = Grams::new(0.0);
+        self.carbs.map(|carbs| {
+            let net = carbs - self.fiber.unwrap_or(zero) - self.sugar_alcohols.unwrap_or(zero);
+            net.max(zero)
+        })
+    }
+}
+
+/* Derived metrics may be missing from stored data; `recalculate()` fills them in after loading. */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct GroceryItem {
-    pub protein: OrderedFloat<f64>,
-    pub calories: OrderedFloat<f64>,
-    pub cost: OrderedFloat<f64>,
-    pub servings: OrderedFloat<f64>,
+    pub protein: Grams,
+    pub calories: Kcal,
+    pub cost: Money,
+    pub servings: Servings,
     pub name: String,
-    pub leanness: OrderedFloat<f64>, // lower is better
-    pub ppd: OrderedFloat<f64>,      // protein per dollar
-    pub lpd: OrderedFloat<f64>,      // leanness per dollar
+    #[serde(default)]
+    pub leanness: KcalPerGram, // lower is better
+    #[serde(default)]
+    pub ppd: GramsPerDollar, // protein per dollar
+    #[serde(default)]
+    pub lpd: LeannessPerDollar, // leanness per dollar
+    #[serde(default)]
+    pub serving_grams: Option<Grams>,
+    #[serde(default)]
+    pub macros: Macros,
+    #[serde(default)]
+    pub quality: Option<ProteinQuality>, // PDCAAS / DIAAS, if known
+    #[serde(default)]
+    pub amino_acids: Option<AminoAcidProfile>, // entered profile; see `amino_profile()`
 }
 
 impl<T, U, V, W> UxItem<T, U, V, W>
 where
-    f64: From<T>,
-    f64: From<U>,
-    f64: From<V>,
-    f64: From<W>,
+    T: Into<Grams>,
+    U: Into<Kcal>,
+    V: Into<Money>,
+    W: Into<Servings>,
 {
     pub fn to_grocery(self) -> GroceryItem {
-        let _protein: OrderedFloat<f64> = OrderedFloat(self.protein.into());
-        let _calories: OrderedFloat<f64> = OrderedFloat(self.calories.into());
-        let _cost: OrderedFloat<f64> = OrderedFloat(self.cost.into());
-        let _servings: OrderedFloat<f64> = OrderedFloat(self.servings.into());
-        let _leanness: OrderedFloat<f64> = calc_leanness(&_protein, &_calories);
-        let _ppd: OrderedFloat<f64> = calc_protein_per_dollar(&_protein, &_cost, &_servings);
-        let _lpd: OrderedFloat<f64> =
+        let _protein: Grams = self.protein.into();
+        let _calories: Kcal = self.calories.into();
+        let _cost: Money = self.cost.into();
+        let _servings: Servings = self.servings.into();
+        let _leanness: KcalPerGram = calc_leanness(&_protein, &_calories);
+        let _ppd: GramsPerDollar = calc_protein_per_dollar(&_protein, &_cost, &_servings);
+        let _lpd: LeannessPerDollar =
             calc_leanness_per_dollar(&_protein, &_calories, &_cost, &_servings);
 
         GroceryItem {
@@ -49,8 +104,65 @@ where
             leanness: _leanness,
             ppd: _ppd,
             lpd: _lpd,
+            serving_grams: self.serving_grams,
+            macros: self.macros,
+            quality: self.quality,
+            amino_acids: self.amino_acids,
         }
     }
+
+    pub fn try_to_grocery(self) -> Result<GroceryItem, MetricError> {
+        let _protein: Grams = self.protein.into();
+        let _calories: Kcal = self.calories.into();
+        let _cost: Money = self.cost.into();
+        let _servings: Servings = self.servings.into();
+        let _leanness = try_calc_leanness(&_protein, &_calories)?;
+        let _ppd = try_calc_protein_per_dollar(&_protein, &_cost, &_servings)?;
+        let _lpd = try_calc_leanness_per_dollar(&_protein, &_calories, &_cost, &_servings)?;
+
+        Ok(GroceryItem {
+            protein: _protein,
+            calories: _calories,
+            cost: _cost,
+            servings: _servings,
+            name: self.name,
+            leanness: _leanness,
+            ppd: _ppd,
+            lpd: _lpd,
+            serving_grams: self.serving_grams,
+            macros: self.macros,
+            quality: self.quality,
+            amino_acids: self.amino_acids,
+        })
+    }
+}
+
+impl<T, U, V> UxItem<T, U, V, Servings>
+where
+    T: Into<Grams>,
+    U: Into<Kcal>,
+    V: Into<Money>,
+{
+    pub fn from_package(
+        name: String,
+        protein: T,
+        calories: U,
+        cost: V,
+        package: &Package,
+    ) -> Result<Self, UnitError> {
+        /* Protein and calories stay per labelled serving; the package sets the count. */
+        Ok(UxItem {
+            protein,
+            calories,
+            cost,
+            servings: package.servings()?,
+            name,
+            serving_grams: Some(package.serving.to_grams(&package.conversion)?),
+            macros: Macros::default(),
+            quality: None,
+            amino_acids: None,
+        })
+    }
 }
 
 impl Display for GroceryItem {
@@ -63,70 +175,1247 @@ impl Display for GroceryItem {
     }
 }
 
-pub fn calc_leanness(
-    protein: &OrderedFloat<f64>,
-    calories: &OrderedFloat<f64>,
-) -> OrderedFloat<f64> {
-    /* Lower is better. Sort ascending! */
-    if *protein == 0.0 {
-        return OrderedFloat(0.0);
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Field {
+    Name,
+    Protein,
+    Calories,
+    Cost,
+    Servings,
+    Fat,
+    Carbs,
+    Fiber,
+    Sugars,
+    AddedSugars,
+    SugarAlcohols,
+    Quality,
+    ServingGrams,
+    AminoAcids,
+}
+
+impl Display for Field {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            Field::Name => "name",
+            Field::Protein => "protein",
+            Field::Calories => "calories",
+            Field::Cost => "cost",
+            Field::Servings => "servings",
+            Field::Fat => "fat",
+            Field::Carbs => "total carbs",
+            Field::Fiber => "fiber",
+            Field::Sugars => "sugars",
+            Field::AddedSugars => "added sugars",
+            Field::SugarAlcohols => "sugar alcohols",
+            Field::Quality => "protein quality score",
+            Field::ServingGrams => "serving size",
+            Field::AminoAcids => "amino acid profile",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricError {
+    ZeroProtein,
+    NonPositiveCost,
+    NonFinite(Field),
+    NaN(Field),
+}
+
+impl MetricError {
+    pub fn field(&self) -> Field {
+        /* The input the user should fix to clear this error. */
+        match self {
+            MetricError::ZeroProtein => Field::Protein,
+            MetricError::NonPositiveCost => Field::Cost,
+            MetricError::NonFinite(field) | MetricError::NaN(field) => *field,
+        }
+    }
+}
+
+impl Display for MetricError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MetricError::ZeroProtein => write!(f, "Protein must be greater than zero"),
+            MetricError::NonPositiveCost => write!(f, "Cost must be greater than zero"),
+            MetricError::NonFinite(field) => write!(f, "The {} value is too large", field),
+            MetricError::NaN(field) => write!(f, "The {} value is not a number", field),
+        }
     }
+}
+
+impl std::error::Error for MetricError {}
+
+pub const MIN_NAME_LEN: usize = 2;
+pub const KCAL_PER_GRAM_PROTEIN: f64 = 4.0;
+pub const KCAL_PER_GRAM_FAT: f64 = 9.0;
+pub const KCAL_PER_GRAM_CARB: f64 = 4.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationIssue {
+    NameTooShort { min: usize },
+    NonPositive(Field),
+    ProteinExceedsCalories { max_protein: f64 },
+    ServingsBelowOne,
+    NegativeCost,
+    NegativeMacro(Field),
+    PartExceedsWhole { part: Field, whole: Field },
+    InvalidQualityScore,
+    InvalidServingGrams,
+    InvalidAminoAcid(EssentialAminoAcid),
+    Metric(MetricError),
+}
+
+impl ValidationIssue {
+    pub fn field(&self) -> Field {
+        match self {
+            ValidationIssue::NameTooShort { .. } => Field::Name,
+            ValidationIssue::NonPositive(field) => *field,
+            ValidationIssue::ProteinExceedsCalories { .. } => Field::Protein,
+            ValidationIssue::ServingsBelowOne => Field::Servings,
+            ValidationIssue::NegativeCost => Field::Cost,
+            ValidationIssue::NegativeMacro(field) => *field,
+            ValidationIssue::PartExceedsWhole { part, .. } => *part,
+            ValidationIssue::InvalidQualityScore => Field::Quality,
+            ValidationIssue::InvalidServingGrams => Field::ServingGrams,
+            ValidationIssue::InvalidAminoAcid(_) => Field::AminoAcids,
+            ValidationIssue::Metric(err) => err.field(),
+        }
+    }
+}
+
+impl Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationIssue::NameTooShort { min } => {
+                write!(f, "Name must be at least {} characters", min)
+            }
+            ValidationIssue::NonPositive(field) => {
+                write!(f, "The {} must be greater than zero", field)
+            }
+            ValidationIssue::ProteinExceedsCalories { max_protein } => write!(
+                f,
+                "Protein can be at most {:.1}g for these calories (4 kCal per gram)",
+                max_protein
+            ),
+            ValidationIssue::ServingsBelowOne => write!(f, "Servings must be at least 1"),
+            ValidationIssue::NegativeCost => write!(f, "Cost can't be negative"),
+            ValidationIssue::NegativeMacro(field) => {
+                write!(f, "The {} must be a number, zero or more", field)
+            }
+            ValidationIssue::PartExceedsWhole { part, whole } => {
+                write!(f, "The {} can't be more than the {}", part, whole)
+            }
+            ValidationIssue::InvalidQualityScore => {
+                write!(f, "The protein quality score must be zero or more")
+            }
+            ValidationIssue::InvalidServingGrams => {
+                write!(f, "The serving size must be more than zero grams")
+            }
+            ValidationIssue::InvalidAminoAcid(amino_acid) => {
+                write!(
+                    f,
+                    "The {} content must be a number, zero or more",
+                    amino_acid
+                )
+            }
+            ValidationIssue::Metric(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl From<MetricError> for ValidationIssue {
+    fn from(err: MetricError) -> Self {
+        ValidationIssue::Metric(err)
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn validate_fields(
+    name: &str,
+    protein: &Grams,
+    calories: &Kcal,
+    cost: &Money,
+    servings: &Servings,
+    serving_grams: &Option<Grams>,
+    macros: &Macros,
+    quality: &Option<ProteinQuality>,
+    amino_acids: &Option<AminoAcidProfile>,
+) -> Vec<ValidationIssue> {
+    let mut issues: Vec<ValidationIssue> = vec![];
+    if name.trim().chars().count() < MIN_NAME_LEN {
+        issues.push(ValidationIssue::NameTooShort { min: MIN_NAME_LEN });
+    }
+    /* NaN compares false both ways, so it's caught here explicitly. */
+    let mut positive = true;
+    for (field, value) in [
+        (Field::Protein, protein.value()),
+        (Field::Calories, calories.value()),
+    ] {
+        if value.is_nan() || value <= 0.0 {
+            issues.push(ValidationIssue::NonPositive(field));
+            positive = false;
+        }
+    }
+    /* Every gram of protein is 4 kCal, so the label can't claim more than calories / 4. */
+    let max_protein = calories.value() / KCAL_PER_GRAM_PROTEIN;
+    if positive && protein.value() > max_protein {
+        issues.push(ValidationIssue::ProteinExceedsCalories { max_protein });
+    }
+    if servings.value() < 1.0 {
+        issues.push(ValidationIssue::ServingsBelowOne);
+    }
+    if cost.value() < 0.0 {
+        issues.push(ValidationIssue::NegativeCost);
+    }
+    if serving_grams.is_some_and(|grams| !(grams.value().is_finite() && grams.value() > 0.0)) {
+        issues.push(ValidationIssue::InvalidServingGrams);
+    }
+    for (field, grams) in macros.entries() {
+        if grams.is_some_and(|grams| !(grams.value().is_finite() && grams.value() >= 0.0)) {
+            issues.push(ValidationIssue::NegativeMacro(field));
+        }
+    }
+    if quality.is_some_and(|quality| !quality.score.is_finite() || quality.score < 0.0) {
+        issues.push(ValidationIssue::InvalidQualityScore);
+    }
+    if let Some(profile) = amino_acids {
+        for amino_acid in EssentialAminoAcid::ALL {
+            let mg_per_g = profile.get(amino_acid);
+            if !(mg_per_g.is_finite() && mg_per_g >= 0.0) {
+                issues.push(ValidationIssue::InvalidAminoAcid(amino_acid));
+            }
+        }
+    }
+    let parts = [
+        (Field::Fiber, macros.fiber, Field::Carbs, macros.carbs),
+        (Field::Sugars, macros.sugars, Field::Carbs, macros.carbs),
+        (
+            Field::SugarAlcohols,
+            macros.sugar_alcohols,
+            Field::Carbs,
+            macros.carbs,
+        ),
+        (
+            Field::AddedSugars,
+            macros.added_sugars,
+            Field::Sugars,
+            macros.sugars,
+        ),
+    ];
+    for (part, part_grams, whole, whole_grams) in parts {
+        if let (Some(part_grams), Some(whole_grams)) = (part_grams, whole_grams)
+            && part_grams > whole_grams
+        {
+            issues.push(ValidationIssue::PartExceedsWhole { part, whole });
+        }
+    }
+
+    /* Anything the rules above let through must still produce finite metrics. */
+    let metric_errors = [
+        try_calc_leanness(protein, calories).err(),
+        try_calc_protein_per_dollar(protein, cost, servings).err(),
+        try_calc_leanness_per_dollar(protein, calories, cost, servings).err(),
+    ];
+    for err in metric_errors.into_iter().flatten() {
+        if !issues.iter().any(|issue| issue.field() == err.field()) {
+            issues.push(err.into());
+        }
+    }
+    issues
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroceryItemBuilder {
+    name: String,
+    protein: Grams,
+    calories: Kcal,
+    cost: Money,
+    servings: Servings,
+    serving_grams: Option<Grams>,
+    macros: Macros,
+    quality: Option<ProteinQuality>,
+    amino_acids: Option<AminoAcidProfile>,
+}
+
+impl Default for GroceryItemBuilder {
+    fn default() -> Self {
+        GroceryItemBuilder {
+            name: "".to_string(),
+            protein: Grams::new(0.0),
+            calories: Kcal::new(0.0),
+            cost: Money::new(0.0),
+            servings: Servings::new(1.0),
+            serving_grams: None,
+            macros: Macros::default(),
+            quality: None,
+            amino_acids: None,
+        }
+    }
+}
+
+impl GroceryItemBuilder {
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    pub fn protein(mut self, protein: Grams) -> Self {
+        self.protein = protein;
+        self
+    }
+
+    pub fn calories(mut self, calories: Kcal) -> Self {
+        self.calories = calories;
+        self
+    }
+
+    pub fn cost(mut self, cost: Money) -> Self {
+        self.cost = cost;
+        self
+    }
+
+    pub fn servings(mut self, servings: Servings) -> Self {
+        self.servings = servings;
+        self
+    }
+
+    pub fn serving_grams(mut self, serving_grams: Option<Grams>) -> Self {
+        self.serving_grams = serving_grams;
+        self
+    }
+
+    pub fn macros(mut self, macros: Macros) -> Self {
+        self.macros = macros;
+        self
+    }
+
+    pub fn quality(mut self, quality: Option<ProteinQuality>) -> Self {
+        self.quality = quality;
+        self
+    }
+
+    pub fn amino_acids(mut self, amino_acids: Option<AminoAcidProfile>) -> Self {
+        self.amino_acids = amino_acids;
+        self
+    }
+
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        validate_fields(
+            &self.name,
+            &self.protein,
+            &self.calories,
+            &self.cost,
+            &self.servings,
+            &self.serving_grams,
+            &self.macros,
+            &self.quality,
+            &self.amino_acids,
+        )
+    }
+
+    pub fn build(self) -> Result<GroceryItem, Vec<ValidationIssue>> {
+        let issues = self.validate();
+        if !issues.is_empty() {
+            return Err(issues);
+        }
+
+        UxItem {
+            name: self.name.trim().to_string(),
+            protein: self.protein,
+            calories: self.calories,
+            cost: self.cost,
+            servings: self.servings,
+            serving_grams: self.serving_grams,
+            macros: self.macros,
+            quality: self.quality,
+            amino_acids: self.amino_acids,
+        }
+        .try_to_grocery()
+        .map_err(|err| vec![err.into()])
+    }
+}
+
+/* Shared by the test modules: four servings, nothing optional; adjust with the setters. */
+#[cfg(test)]
+pub(crate) fn test_builder(
+    name: &str,
+    protein: f64,
+    calories: f64,
+    cost: f64,
+) -> GroceryItemBuilder {
+    GroceryItem::builder()
+        .name(name)
+        .protein(Grams::new(protein))
+        .calories(Kcal::new(calories))
+        .cost(Money::new(cost))
+        .servings(Servings::new(4.0))
+}
+
+#[cfg(test)]
+pub(crate) fn test_item(name: &str, protein: f64, calories: f64, cost: f64) -> GroceryItem {
+    test_builder(name, protein, calories, cost)
+        .build()
+        .expect("test items are valid")
+}
+
+impl GroceryItem {
+    pub fn builder() -> GroceryItemBuilder {
+        GroceryItemBuilder::default()
+    }
+
+    pub fn recalculate(&mut self) {
+        /* Bring the stored metrics back in line after protein, calories, cost or servings change. */
+        self.leanness = calc_leanness(&self.protein, &self.calories);
+        self.ppd = calc_protein_per_dollar(&self.protein, &self.cost, &self.servings);
+        self.lpd =
+            calc_leanness_per_dollar(&self.protein, &self.calories, &self.cost, &self.servings);
+    }
+
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        validate_fields(
+            &self.name,
+            &self.protein,
+            &self.calories,
+            &self.cost,
+            &self.servings,
+            &self.serving_grams,
+            &self.macros,
+            &self.quality,
+            &self.amino_acids,
+        )
+    }
+
+    pub fn digestible_protein(&self) -> Option<Grams> {
+        self.quality.map(|quality| self.protein * quality.factor())
+    }
+
+    pub fn digestible_ppd(&self) -> Option<GramsPerDollar> {
+        /* Higher is better, like ppd. */
+        self.quality
+            .map(|quality| GramsPerDollar::new(self.ppd.value() * quality.factor()))
+    }
+
+    pub fn kcal_per_digestible_gram(&self) -> Option<KcalPerGram> {
+        /* Lower is better, like leanness. A score of zero has no digestible protein. */
+        let digestible = self.digestible_protein()?;
+        if digestible.value() > 0.0 {
+            Some(self.calories / digestible)
+        } else {
+            None
+        }
+    }
+
+    pub fn net_carbs(&self) -> Option<Grams> {
+        self.macros.net_carbs()
+    }
+
+    fn pct_of_calories(&self, grams: Grams, kcal_per_gram: f64) -> Option<f64> {
+        let calories = self.calories.value();
+        if calories > 0.0 {
+            Some(grams.value() * kcal_per_gram / calories * 100.0)
+        } else {
+            None
+        }
+    }
+
+    pub fn pct_calories_from_protein(&self) -> Option<f64> {
+        self.pct_of_calories(self.protein, KCAL_PER_GRAM_PROTEIN)
+    }
+
+    pub fn pct_calories_from_fat(&self) -> Option<f64> {
+        self.pct_of_calories(self.macros.fat?, KCAL_PER_GRAM_FAT)
+    }
+
+    pub fn pct_calories_from_carbs(&self) -> Option<f64> {
+        self.pct_of_calories(self.macros.carbs?, KCAL_PER_GRAM_CARB)
+    }
+
+    pub fn package_protein(&self) -> Grams {
+        self.protein * self.servings
+    }
+
+    pub fn cost_per_serving(&self) -> Money {
+        self.cost / self.servings
+    }
+
+    pub fn package_grams(&self) -> Option<Grams> {
+        self.serving_grams.map(|grams| grams * self.servings)
+    }
+
+    fn per_100g_factor(&self) -> Option<f64> {
+        /* Scales one serving up (or down) to 100 g of product. */
+        self.serving_grams
+            .map(|grams| grams.value())
+            .filter(|grams| *grams > 0.0)
+            .map(|grams| 100.0 / grams)
+    }
+
+    pub fn protein_per_100g(&self) -> Option<Grams> {
+        self.per_100g_factor().map(|factor| self.protein * factor)
+    }
+
+    pub fn kcal_per_100g(&self) -> Option<Kcal> {
+        self.per_100g_factor().map(|factor| self.calories * factor)
+    }
+
+    pub fn cost_per_100g(&self) -> Option<Money> {
+        /* Scale the whole package price once so rounding to cents happens a single time. */
+        self.package_grams()
+            .map(|grams| grams.value())
+            .filter(|grams| *grams > 0.0)
+            .map(|grams| self.cost * (100.0 / grams))
+    }
+}
+
+/* Serialized as `key()`, so files and the CLI spell metrics the same way. */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Metric {
+    Leanness,
+    Ppd,
+    Lpd,
+    Cost,
+    CostPerServing,
+    #[serde(rename = "protein-per-100g")]
+    ProteinPer100g,
+    #[serde(rename = "cost-per-100g")]
+    CostPer100g,
+    DigestiblePpd,
+    #[serde(rename = "kcal-per-digestible-g")]
+    KcalPerDigestibleGram,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 9] = [
+        Metric::Leanness,
+        Metric::Ppd,
+        Metric::Lpd,
+        Metric::Cost,
+        Metric::CostPerServing,
+        Metric::ProteinPer100g,
+        Metric::CostPer100g,
+        Metric::DigestiblePpd,
+        Metric::KcalPerDigestibleGram,
+    ];
 
-    let result = calories / protein;
-    if result.is_infinite() {
-        panic!("Result is infinite");
-    } else if result.is_nan() {
-        panic!("Result is not a number (NaN).");
+    pub fn key(&self) -> &'static str {
+        match self {
+            Metric::Leanness => "leanness",
+            Metric::Ppd => "ppd",
+            Metric::Lpd => "lpd",
+            Metric::Cost => "cost",
+            Metric::CostPerServing => "cost-per-serving",
+            Metric::ProteinPer100g => "protein-per-100g",
+            Metric::CostPer100g => "cost-per-100g",
+            Metric::DigestiblePpd => "digestible-ppd",
+            Metric::KcalPerDigestibleGram => "kcal-per-digestible-g",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Metric::Leanness => "Leanness (kCal / g protein)",
+            Metric::Ppd => "Protein Per Dollar",
+            Metric::Lpd => "Leanness Per Dollar",
+            Metric::Cost => "Cost",
+            Metric::CostPerServing => "Cost / Serving",
+            Metric::ProteinPer100g => "Protein / 100g",
+            Metric::CostPer100g => "Cost / 100g",
+            Metric::DigestiblePpd => "Digestible Protein Per Dollar",
+            Metric::KcalPerDigestibleGram => "kCal / Digestible g",
+        }
+    }
+
+    pub fn higher_is_better(&self) -> bool {
+        match self {
+            Metric::Ppd | Metric::ProteinPer100g | Metric::DigestiblePpd => true,
+            Metric::Leanness
+            | Metric::Lpd
+            | Metric::Cost
+            | Metric::CostPerServing
+            | Metric::CostPer100g
+            | Metric::KcalPerDigestibleGram => false,
+        }
+    }
+
+    pub fn value(&self, item: &GroceryItem) -> Option<f64> {
+        /* None when the item lacks the inputs this metric needs. */
+        match self {
+            Metric::Leanness => Some(item.leanness.value()),
+            Metric::Ppd => Some(item.ppd.value()),
+            Metric::Lpd => Some(item.lpd.value()),
+            Metric::Cost => Some(item.cost.value()),
+            Metric::CostPerServing => Some(item.cost_per_serving().value()),
+            Metric::ProteinPer100g => item.protein_per_100g().map(|grams| grams.value()),
+            Metric::CostPer100g => item.cost_per_100g().map(|cost| cost.value()),
+            Metric::DigestiblePpd => item.digestible_ppd().map(|ppd| ppd.value()),
+            Metric::KcalPerDigestibleGram => {
+                item.kcal_per_digestible_gram().map(|kcal| kcal.value())
+            }
+        }
+    }
+}
+
+impl Display for Metric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.key())
+    }
+}
+
+impl std::str::FromStr for Metric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let wanted = s.trim().to_lowercase();
+        Metric::ALL
+            .into_iter()
+            .find(|metric| metric.key() == wanted)
+            .ok_or_else(|| format!("'{}' is not a known metric", s.trim()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortMode {
+    #[default]
+    Insertion,
+    Leanness,
+    Ppd,
+    Lpd,
+    Cost,
+    Name,
+}
+
+impl SortMode {
+    pub const ALL: [SortMode; 6] = [
+        SortMode::Insertion,
+        SortMode::Leanness,
+        SortMode::Ppd,
+        SortMode::Lpd,
+        SortMode::Cost,
+        SortMode::Name,
+    ];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            SortMode::Insertion => "insertion",
+            SortMode::Leanness => "leanness",
+            SortMode::Ppd => "ppd",
+            SortMode::Lpd => "lpd",
+            SortMode::Cost => "cost",
+            SortMode::Name => "name",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SortMode::Insertion => "Order Added",
+            SortMode::Leanness => "Leanness",
+            SortMode::Ppd => "Protein Per Dollar",
+            SortMode::Lpd => "Leanness Per Dollar",
+            SortMode::Cost => "Cost",
+            SortMode::Name => "Name",
+        }
+    }
+
+    pub fn metric(&self) -> Option<Metric> {
+        match self {
+            SortMode::Leanness => Some(Metric::Leanness),
+            SortMode::Ppd => Some(Metric::Ppd),
+            SortMode::Lpd => Some(Metric::Lpd),
+            SortMode::Cost => Some(Metric::Cost),
+            SortMode::Insertion | SortMode::Name => None,
+        }
+    }
+
+    pub fn better(&self) -> &'static str {
+        match self.metric() {
+            Some(metric) if metric.higher_is_better() => "higher is better",
+            Some(_) => "lower is better",
+            None => "no better or worse",
+        }
+    }
+
+    pub fn best_first(&self) -> SortOrder {
+        /* Metrics open with the best item on top; name and insertion read top down. */
+        SortOrder {
+            mode: *self,
+            descending: self
+                .metric()
+                .is_some_and(|metric| metric.higher_is_better()),
+        }
+    }
+}
+
+impl Display for SortMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.key())
+    }
+}
+
+impl std::str::FromStr for SortMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let wanted = s.trim().to_lowercase();
+        SortMode::ALL
+            .into_iter()
+            .find(|mode| mode.key() == wanted)
+            .ok_or_else(|| format!("'{}' is not a known sort", s.trim()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SortOrder {
+    pub mode: SortMode,
+    pub descending: bool,
+}
+
+impl SortOrder {
+    pub fn sort(&self, items: &[GroceryItem]) -> Vec<usize> {
+        /* Indices in display order. Stable, so ties keep the order they were added in. */
+        let mut order: Vec<usize> = (0..items.len()).collect();
+        let direction = |ordering: std::cmp::Ordering| {
+            if self.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        };
+        match self.mode {
+            SortMode::Insertion => {
+                if self.descending {
+                    order.reverse();
+                }
+            }
+            SortMode::Name => {
+                let names: Vec<String> =
+                    items.iter().map(|item| item.name.to_lowercase()).collect();
+                order.sort_by(|a, b| direction(names[*a].cmp(&names[*b])));
+            }
+            _ => {
+                let metric = self.mode.metric().unwrap_or(Metric::Leanness);
+                let values: Vec<Option<f64>> =
+                    items.iter().map(|item| metric.value(item)).collect();
+                /* Items missing the value sink to the bottom either way. */
+                order.sort_by(|a, b| match (values[*a], values[*b]) {
+                    (Some(a_value), Some(b_value)) => direction(a_value.total_cmp(&b_value)),
+                    (Some(_), None) => std::cmp::Ordering::Less,
+                    (None, Some(_)) => std::cmp::Ordering::Greater,
+                    (None, None) => std::cmp::Ordering::Equal,
+                });
+            }
+        }
+        order
+    }
+
+    pub fn describe(&self) -> String {
+        let direction = match (self.mode, self.descending) {
+            (SortMode::Insertion, false) => "oldest first",
+            (SortMode::Insertion, true) => "newest first",
+            (SortMode::Name, false) => "A to Z",
+            (SortMode::Name, true) => "Z to A",
+            (_, false) => "lowest first",
+            (_, true) => "highest first",
+        };
+        format!(
+            "{}, {} ({})",
+            self.mode.label(),
+            direction,
+            self.mode.better()
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AtwaterFactors {
+    pub protein: f64,
+    pub fat: f64,
+    pub carbs: f64,
+    pub fiber: f64,
+    pub sugar_alcohols: f64,
+}
+
+impl AtwaterFactors {
+    /* The 4/9/4 factors labels are built from; fiber and sugar alcohols count as carbs. */
+    pub const GENERAL: AtwaterFactors = AtwaterFactors {
+        protein: KCAL_PER_GRAM_PROTEIN,
+        fat: KCAL_PER_GRAM_FAT,
+        carbs: KCAL_PER_GRAM_CARB,
+        fiber: KCAL_PER_GRAM_CARB,
+        sugar_alcohols: KCAL_PER_GRAM_CARB,
+    };
+
+    /* Closer to what bars with added fiber and polyols actually deliver. */
+    pub const ADJUSTED: AtwaterFactors = AtwaterFactors {
+        protein: KCAL_PER_GRAM_PROTEIN,
+        fat: KCAL_PER_GRAM_FAT,
+        carbs: KCAL_PER_GRAM_CARB,
+        fiber: 2.0,
+        sugar_alcohols: 2.4,
+    };
+
+    pub fn estimate(&self, protein: Grams, macros: &Macros) -> Option<Kcal> {
+        /* Without fat and carbs there's nothing meaningful to compare against. */
+        let fat = macros.fat?.value();
+        let carbs = macros.carbs?.value();
+        let fiber = macros.fiber.map_or(0.0, |grams| grams.value());
+        let sugar_alcohols = macros.sugar_alcohols.map_or(0.0, |grams| grams.value());
+        let digestible_carbs = (carbs - fiber - sugar_alcohols).max(0.0);
+
+        Some(Kcal::new(
+            protein.value() * self.protein
+                + fat * self.fat
+                + digestible_carbs * self.carbs
+                + fiber * self.fiber
+                + sugar_alcohols * self.sugar_alcohols,
+        ))
+    }
+}
+
+impl Default for AtwaterFactors {
+    fn default() -> Self {
+        AtwaterFactors::GENERAL
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LabelCheck {
+    pub labelled: Kcal,
+    pub estimated: Kcal,
+    pub difference: Kcal, // estimated - labelled
+    pub pct_off: f64,     // |difference| as a percent of the labelled calories
+    pub flagged: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LabelChecker {
+    pub factors: AtwaterFactors,
+    pub tolerance_pct: f64,
+}
+
+impl Default for LabelChecker {
+    fn default() -> Self {
+        /* Nutrition labels are allowed to be off by up to 20%. */
+        LabelChecker {
+            factors: AtwaterFactors::GENERAL,
+            tolerance_pct: 20.0,
+        }
+    }
+}
+
+impl LabelChecker {
+    pub fn check(&self, item: &GroceryItem) -> Option<LabelCheck> {
+        let estimated = self.factors.estimate(item.protein, &item.macros)?;
+        let labelled = item.calories;
+        let difference = estimated - labelled;
+        let pct_off = if labelled.value() > 0.0 {
+            difference.value().abs() / labelled.value() * 100.0
+        } else {
+            100.0
+        };
+
+        Some(LabelCheck {
+            labelled,
+            estimated,
+            difference,
+            pct_off,
+            flagged: pct_off > self.tolerance_pct,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BreakEven {
+    pub metric: Metric,
+    pub field: Field,     // what was solved for: cost, servings or protein
+    pub current: f64,     // the item's value for `field` today
+    pub threshold: f64,   // the value of `field` that ties the rival on `metric`
+    pub rival_value: f64, // the rival's `metric`, which the item has to match
+    pub wins_above: bool, // true: the item wins once `field` is above `threshold`
+}
+
+impl BreakEven {
+    pub fn wins_now(&self) -> bool {
+        if self.wins_above {
+            self.current > self.threshold
+        } else {
+            self.current < self.threshold
+        }
+    }
+
+    pub fn threshold_cost(&self) -> Option<Money> {
+        /*
+        The nearest whole-cent price that still wins outright. A threshold that lands exactly
+        on a cent only ties there, so step one cent past it.
+        */
+        if self.field != Field::Cost || !self.threshold.is_finite() {
+            return None;
+        }
+        let cents = self.threshold * 100.0;
+        let on_cent = (cents - cents.round()).abs() < 1e-6;
+        let winning = match (self.wins_above, on_cent) {
+            (false, true) => cents.round() - 1.0,
+            (false, false) => cents.floor(),
+            (true, true) => cents.round() + 1.0,
+            (true, false) => cents.ceil(),
+        };
+        Some(Money::from_cents(winning as i64))
+    }
+}
+
+impl Display for BreakEven {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let direction = if self.wins_above { "above" } else { "below" };
+        match self.threshold_cost() {
+            Some(cost) => write!(
+                f,
+                "Wins on {} at or {} ${}",
+                self.metric.label(),
+                direction,
+                cost
+            ),
+            None => write!(
+                f,
+                "Wins on {} with {} {} {:.2}",
+                self.metric.label(),
+                self.field,
+                direction,
+                self.threshold
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakEvenError {
+    Unaffected { metric: Metric, field: Field }, // `field` doesn't move `metric`
+    MissingValue(Metric),
+    ZeroValue(Metric),
+}
+
+impl Display for BreakEvenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BreakEvenError::Unaffected { metric, field } => {
+                write!(f, "Changing {} doesn't change {}", field, metric.label())
+            }
+            BreakEvenError::MissingValue(metric) => {
+                write!(
+                    f,
+                    "Both items need what {} is calculated from",
+                    metric.label()
+                )
+            }
+            BreakEvenError::ZeroValue(metric) => {
+                write!(
+                    f,
+                    "{} is zero, so there is nothing to break even with",
+                    metric.label()
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for BreakEvenError {}
+
+fn break_even_exponent(metric: Metric, field: Field) -> i32 {
+    /*
+    Every metric is some constant times cost, servings or protein raised to 1 or -1
+    (or not involving it at all), which is what makes the break-even point exact.
+    */
+    match (field, metric) {
+        (Field::Cost, Metric::Cost | Metric::CostPerServing | Metric::CostPer100g) => 1,
+        (Field::Cost, Metric::Ppd | Metric::Lpd | Metric::DigestiblePpd) => -1,
+        (Field::Servings, Metric::Ppd | Metric::Lpd | Metric::DigestiblePpd) => 1,
+        (Field::Servings, Metric::CostPerServing | Metric::CostPer100g) => -1,
+        (Field::Protein, Metric::Ppd | Metric::DigestiblePpd | Metric::ProteinPer100g) => 1,
+        (Field::Protein, Metric::Leanness | Metric::Lpd | Metric::KcalPerDigestibleGram) => -1,
+        _ => 0,
+    }
+}
+
+pub fn break_even(
+    item: &GroceryItem,
+    rival: &GroceryItem,
+    metric: Metric,
+    field: Field,
+) -> Result<BreakEven, BreakEvenError> {
+    /* What `field` on `item` would have to be for it to tie `rival` on `metric`. */
+    let exponent = break_even_exponent(metric, field);
+    if exponent == 0 {
+        return Err(BreakEvenError::Unaffected { metric, field });
+    }
+    let current = match field {
+        Field::Cost => item.cost.value(),
+        Field::Servings => item.servings.value(),
+        _ => item.protein.value(),
+    };
+    let value = metric
+        .value(item)
+        .ok_or(BreakEvenError::MissingValue(metric))?;
+    let rival_value = metric
+        .value(rival)
+        .ok_or(BreakEvenError::MissingValue(metric))?;
+    if value == 0.0 || rival_value == 0.0 || current == 0.0 {
+        return Err(BreakEvenError::ZeroValue(metric));
+    }
+
+    let threshold = current * (rival_value / value).powi(exponent);
+    Ok(BreakEven {
+        metric,
+        field,
+        current,
+        threshold,
+        rival_value,
+        wins_above: metric.higher_is_better() == (exponent > 0),
+    })
+}
+
+fn check_input(value: f64, field: Field) -> Result<(), MetricError> {
+    if value.is_nan() {
+        Err(MetricError::NaN(field))
+    } else if value.is_infinite() {
+        Err(MetricError::NonFinite(field))
     } else {
-        result
+        Ok(())
     }
 }
 
-pub fn calc_protein_per_dollar(
-    protein: &OrderedFloat<f64>,
-    cost: &OrderedFloat<f64>,
-    servings: &OrderedFloat<f64>,
-) -> OrderedFloat<f64> {
+fn check_result(result: f64, field: Field) -> Result<f64, MetricError> {
+    /* Inputs are already finite, so a bad result comes from dividing by a tiny `field`. */
+    check_input(result, field).map(|_| result)
+}
+
+pub fn try_calc_leanness(protein: &Grams, calories: &Kcal) -> Result<KcalPerGram, MetricError> {
+    /* Lower is better. Sort ascending! */
+    check_input(protein.value(), Field::Protein)?;
+    check_input(calories.value(), Field::Calories)?;
+    if protein.value() == 0.0 {
+        return Err(MetricError::ZeroProtein);
+    }
+
+    check_result((*calories / *protein).value(), Field::Protein).map(KcalPerGram::new)
+}
+
+pub fn try_calc_protein_per_dollar(
+    protein: &Grams,
+    cost: &Money,
+    servings: &Servings,
+) -> Result<GramsPerDollar, MetricError> {
     /* Higher is better. Sort descending! */
-    if *cost <= OrderedFloat(0.0) {
-        return OrderedFloat(0.0);
+    check_input(protein.value(), Field::Protein)?;
+    check_input(cost.value(), Field::Cost)?;
+    check_input(servings.value(), Field::Servings)?;
+    if cost.value() <= 0.0 {
+        return Err(MetricError::NonPositiveCost);
     }
 
-    let result = (protein * servings) / cost;
+    let _total_protein: Grams = *protein * *servings;
+    check_result((_total_protein / *cost).value(), Field::Cost).map(GramsPerDollar::new)
+}
 
-    if result.is_infinite() {
-        panic!("Result is infinite");
-    } else if result.is_nan() {
-        panic!("Result is not a number (NaN).");
-    } else {
-        result
+pub fn try_calc_leanness_per_dollar(
+    protein: &Grams,
+    calories: &Kcal,
+    cost: &Money,
+    servings: &Servings,
+) -> Result<LeannessPerDollar, MetricError> {
+    /* Lower is better. Sort ascending! */
+    check_input(cost.value(), Field::Cost)?;
+    check_input(servings.value(), Field::Servings)?;
+    if cost.value() <= 0.0 {
+        return Err(MetricError::NonPositiveCost);
     }
+
+    let _leanness = try_calc_leanness(protein, calories)?;
+    /* Divide by the exact package price, not a cost per serving rounded to cents. */
+    let _lpd: LeannessPerDollar = _leanness / *cost;
+
+    check_result(_lpd.value() * servings.value(), Field::Cost).map(LeannessPerDollar::new)
+}
+
+pub fn calc_leanness(protein: &Grams, calories: &Kcal) -> KcalPerGram {
+    /* Lower is better. Sort ascending! Invalid input scores 0.0. */
+    try_calc_leanness(protein, calories).unwrap_or_default()
+}
+
+pub fn calc_protein_per_dollar(
+    protein: &Grams,
+    cost: &Money,
+    servings: &Servings,
+) -> GramsPerDollar {
+    /* Higher is better. Sort descending! Invalid input scores 0.0. */
+    try_calc_protein_per_dollar(protein, cost, servings).unwrap_or_default()
 }
 
 pub fn calc_leanness_per_dollar(
-    protein: &OrderedFloat<f64>,
-    calories: &OrderedFloat<f64>,
-    cost: &OrderedFloat<f64>,
-    servings: &OrderedFloat<f64>,
-) -> OrderedFloat<f64> {
-    /* Higher is better. Sort ascending! */
-    if *cost == 0.0 {
-        return OrderedFloat(0.0);
+    protein: &Grams,
+    calories: &Kcal,
+    cost: &Money,
+    servings: &Servings,
+) -> LeannessPerDollar {
+    /* Lower is better. Sort ascending! Invalid input scores 0.0. */
+    try_calc_leanness_per_dollar(protein, calories, cost, servings).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(protein: f64, calories: f64) -> GroceryItemBuilder {
+        test_builder("Test item", protein, calories, 5.0)
+    }
+
+    #[test]
+    fn negative_protein_is_rejected() {
+        let issues = item(-10.0, 100.0).build().unwrap_err();
+        assert!(issues.contains(&ValidationIssue::NonPositive(Field::Protein)));
     }
 
-    if *calories == 0.0 {
-        return OrderedFloat(0.0);
+    #[test]
+    fn negative_protein_and_calories_skip_the_label_check() {
+        let issues = item(-10.0, -100.0).validate();
+        assert!(issues.contains(&ValidationIssue::NonPositive(Field::Protein)));
+        assert!(issues.contains(&ValidationIssue::NonPositive(Field::Calories)));
+        assert!(
+            !issues
+                .iter()
+                .any(|issue| matches!(issue, ValidationIssue::ProteinExceedsCalories { .. }))
+        );
     }
 
-    let _leanness = calc_leanness(&protein, &calories);
+    #[test]
+    fn nan_protein_is_rejected() {
+        let issues = item(f64::NAN, 100.0).validate();
+        assert!(issues.contains(&ValidationIssue::NonPositive(Field::Protein)));
+    }
 
-    let result = (_leanness * servings) / cost;
+    #[test]
+    fn serving_grams_must_be_positive() {
+        for grams in [-5.0, 0.0, f64::NAN, f64::INFINITY] {
+            let issues = item(20.0, 100.0)
+                .serving_grams(Some(Grams::new(grams)))
+                .validate();
+            assert_eq!(issues, vec![ValidationIssue::InvalidServingGrams]);
+        }
+        assert!(
+            item(20.0, 100.0)
+                .serving_grams(Some(Grams::new(30.0)))
+                .build()
+                .is_ok()
+        );
+    }
 
-    if result.is_infinite() {
-        panic!("Result is infinite");
-    } else if result.is_nan() {
-        panic!("Result is not a number (NaN).");
-    } else {
-        result
+    #[test]
+    fn macros_must_be_finite_and_not_negative() {
+        for grams in [-1.0, f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            let issues = item(20.0, 100.0)
+                .macros(Macros {
+                    fat: Some(Grams::new(grams)),
+                    ..Macros::default()
+                })
+                .validate();
+            assert_eq!(issues, vec![ValidationIssue::NegativeMacro(Field::Fat)]);
+        }
+    }
+
+    #[test]
+    fn amino_acids_must_be_finite_and_not_negative() {
+        for mg_per_g in [-1.0, f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            let mut profile = AminoAcidProfile::REFERENCE;
+            profile.set(EssentialAminoAcid::Lysine, mg_per_g);
+            let issues = item(20.0, 100.0).amino_acids(Some(profile)).validate();
+            assert_eq!(
+                issues,
+                vec![ValidationIssue::InvalidAminoAcid(
+                    EssentialAminoAcid::Lysine
+                )]
+            );
+        }
+        assert!(
+            item(20.0, 100.0)
+                .amino_acids(Some(AminoAcidProfile::REFERENCE))
+                .build()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn valid_item_builds() {
+        assert!(item(20.0, 100.0).build().is_ok());
+    }
+
+    fn cost_break_even(threshold: f64, wins_above: bool) -> BreakEven {
+        BreakEven {
+            metric: Metric::Ppd,
+            field: Field::Cost,
+            current: 5.0,
+            threshold,
+            rival_value: 16.0,
+            wins_above,
+        }
+    }
+
+    #[test]
+    fn lpd_sorts_lowest_first() {
+        let lean = item(30.0, 120.0).name("Lean").build().unwrap();
+        let fatty = item(10.0, 200.0).name("Fatty").build().unwrap();
+        assert!(lean.lpd.value() < fatty.lpd.value());
+        let order = SortMode::Lpd.best_first();
+        assert!(!order.descending);
+        assert_eq!(order.sort(&[fatty, lean]), vec![1, 0]);
+    }
+
+    #[test]
+    fn threshold_cost_steps_past_an_exact_tie() {
+        assert_eq!(
+            cost_break_even(4.0, false).threshold_cost(),
+            Some(Money::from_cents(399))
+        );
+        assert_eq!(
+            cost_break_even(4.0, true).threshold_cost(),
+            Some(Money::from_cents(401))
+        );
+    }
+
+    #[test]
+    fn threshold_cost_rounds_toward_winning() {
+        assert_eq!(
+            cost_break_even(4.005, false).threshold_cost(),
+            Some(Money::from_cents(400))
+        );
+        assert_eq!(
+            cost_break_even(4.005, true).threshold_cost(),
+            Some(Money::from_cents(401))
+        );
+    }
+
+    #[test]
+    fn lpd_break_even_points_the_way_the_metric_does() {
+        let a = item(20.0, 100.0).cost(Money::new(8.0)).build().unwrap();
+        let b = item(20.0, 100.0).cost(Money::new(4.0)).build().unwrap();
+        let even = break_even(&a, &b, Metric::Lpd, Field::Cost).unwrap();
+        assert!((even.threshold - 4.0).abs() < 1e-9);
+        /* Lpd divides by cost and lower is better, so a higher price is what wins it. */
+        assert!(even.wins_above);
+        assert!(even.wins_now());
+        let cost = even.threshold_cost().unwrap();
+        assert_eq!(cost, Money::from_cents(401));
+        let priced = item(20.0, 100.0).cost(cost).build().unwrap();
+        assert!(priced.lpd < b.lpd);
+    }
+
+    #[test]
+    fn equal_items_tie_at_the_current_cost() {
+        let a = item(20.0, 100.0).build().unwrap();
+        let b = item(20.0, 100.0).build().unwrap();
+        let even = break_even(&a, &b, Metric::Ppd, Field::Cost).unwrap();
+        assert!(!even.wins_now());
+        assert_eq!(even.threshold_cost(), Some(Money::from_cents(499)));
     }
 }
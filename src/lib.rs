@@ -1,2 +1,17 @@
+pub mod amino;
+pub mod csv_io;
 pub mod eng;
+pub mod list;
+pub mod optimizer;
+pub mod pareto;
+pub mod persist;
+pub mod qr;
+pub mod quality;
+pub mod quantity;
+pub mod scoring;
+pub mod sensitivity;
+pub mod session;
+pub mod share;
+pub mod storage;
+pub mod units;
 // pub mod protein_calculator;
@@ -0,0 +1,409 @@
+use crate::eng::GroceryItem;
+use std::fmt::Display;
+
+/* Enough to back out of a long editing session without holding every list ever seen. */
+pub const HISTORY_LIMIT: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct ItemId(pub u64);
+
+impl Display for ItemId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub id: ItemId,
+    pub item: GroceryItem,
+}
+
+/* What the user asked for, by id; turned into index-based `Change`s when applied. */
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Add(GroceryItem),
+    Edit(ItemId, GroceryItem),
+    Remove(ItemId),
+    Move { id: ItemId, to: usize },
+    Clear,
+    Load(Vec<GroceryItem>),   // replace everything, e.g. with a saved list
+    Extend(Vec<GroceryItem>), // add several at once, undone together
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListError {
+    UnknownItem(ItemId),
+}
+
+impl Display for ListError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ListError::UnknownItem(id) => write!(f, "Item {} is no longer in the list", id),
+        }
+    }
+}
+
+impl std::error::Error for ListError {}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Change {
+    Insert { index: usize, entry: Entry },
+    Remove { index: usize },
+    Replace { index: usize, entry: Entry },
+    Move { from: usize, to: usize },
+    Reset(Vec<Entry>),
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ItemList {
+    entries: Vec<Entry>,
+    next_id: u64,
+    undo: Vec<Change>, // inverses of applied changes, newest last
+    redo: Vec<Change>,
+}
+
+impl ItemList {
+    pub fn new() -> Self {
+        ItemList::default()
+    }
+
+    pub fn from_items(items: impl IntoIterator<Item = GroceryItem>) -> Self {
+        /* A fresh list with no history, e.g. when loading saved items. */
+        let mut list = ItemList::new();
+        for item in items {
+            let id = list.allocate_id();
+            list.entries.push(Entry { id, item });
+        }
+        list
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn items(&self) -> Vec<GroceryItem> {
+        self.entries
+            .iter()
+            .map(|entry| entry.item.clone())
+            .collect()
+    }
+
+    pub fn ids(&self) -> Vec<ItemId> {
+        self.entries.iter().map(|entry| entry.id).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn position(&self, id: ItemId) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.id == id)
+    }
+
+    pub fn get(&self, id: ItemId) -> Option<&GroceryItem> {
+        self.position(id).map(|index| &self.entries[index].item)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    fn allocate_id(&mut self) -> ItemId {
+        /* Ids are never reused, even after undo, so a stale id can't hit a different item. */
+        let id = ItemId(self.next_id);
+        self.next_id += 1;
+        id
+    }
+
+    fn index_of(&self, id: ItemId) -> Result<usize, ListError> {
+        self.position(id).ok_or(ListError::UnknownItem(id))
+    }
+
+    pub fn apply(&mut self, command: Command) -> Result<ItemId, ListError> {
+        /* Returns the id the command touched; for `Clear`, `Load` and `Extend`, the first id not yet in use before it ran. */
+        let (change, id) = match command {
+            Command::Add(item) => {
+                let id = self.allocate_id();
+                let index = self.entries.len();
+                (
+                    Change::Insert {
+                        index,
+                        entry: Entry { id, item },
+                    },
+                    id,
+                )
+            }
+            Command::Edit(id, item) => {
+                let index = self.index_of(id)?;
+                (
+                    Change::Replace {
+                        index,
+                        entry: Entry { id, item },
+                    },
+                    id,
+                )
+            }
+            Command::Remove(id) => (
+                Change::Remove {
+                    index: self.index_of(id)?,
+                },
+                id,
+            ),
+            Command::Move { id, to } => {
+                let from = self.index_of(id)?;
+                let to = to.min(self.entries.len().saturating_sub(1));
+                if from == to {
+                    /* Nothing moved, so there is nothing to undo and redo stays intact. */
+                    return Ok(id);
+                }
+                (Change::Move { from, to }, id)
+            }
+            Command::Clear => (Change::Reset(vec![]), ItemId(self.next_id)),
+            Command::Load(items) => {
+                let next = ItemId(self.next_id);
+                let entries = items
+                    .into_iter()
+                    .map(|item| Entry {
+                        id: self.allocate_id(),
+                        item,
+                    })
+                    .collect();
+                (Change::Reset(entries), next)
+            }
+            Command::Extend(items) => {
+                let next = ItemId(self.next_id);
+                let mut entries = self.entries.clone();
+                for item in items {
+                    let id = self.allocate_id();
+                    entries.push(Entry { id, item });
+                }
+                (Change::Reset(entries), next)
+            }
+        };
+        let inverse = self.perform(change);
+        self.undo.push(inverse);
+        if self.undo.len() > HISTORY_LIMIT {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+        Ok(id)
+    }
+
+    pub fn undo(&mut self) -> bool {
+        match self.undo.pop() {
+            Some(change) => {
+                let inverse = self.perform(change);
+                self.redo.push(inverse);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn redo(&mut self) -> bool {
+        match self.redo.pop() {
+            Some(change) => {
+                let inverse = self.perform(change);
+                self.undo.push(inverse);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn perform(&mut self, change: Change) -> Change {
+        /* Apply `change` and return the change that puts things back. */
+        match change {
+            Change::Insert { index, entry } => {
+                self.entries.insert(index, entry);
+                Change::Remove { index }
+            }
+            Change::Remove { index } => {
+                let entry = self.entries.remove(index);
+                Change::Insert { index, entry }
+            }
+            Change::Replace { index, entry } => {
+                let entry = std::mem::replace(&mut self.entries[index], entry);
+                Change::Replace { index, entry }
+            }
+            Change::Move { from, to } => {
+                let entry = self.entries.remove(from);
+                self.entries.insert(to, entry);
+                Change::Move { from: to, to: from }
+            }
+            Change::Reset(entries) => Change::Reset(std::mem::replace(&mut self.entries, entries)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eng::test_item;
+
+    fn names(list: &ItemList) -> Vec<String> {
+        list.entries()
+            .iter()
+            .map(|entry| entry.item.name.clone())
+            .collect()
+    }
+
+    fn abc() -> ItemList {
+        ItemList::from_items([
+            test_item("AA", 20.0, 100.0, 5.0),
+            test_item("BB", 20.0, 100.0, 5.0),
+            test_item("CC", 20.0, 100.0, 5.0),
+        ])
+    }
+
+    fn assert_undo_redo(mut list: ItemList, command: Command, after: &[&str]) {
+        let before = names(&list);
+        list.apply(command).unwrap();
+        assert_eq!(names(&list), after);
+        assert!(list.undo());
+        assert_eq!(names(&list), before);
+        assert!(list.redo());
+        assert_eq!(names(&list), after);
+    }
+
+    #[test]
+    fn add_undoes_and_redoes() {
+        assert_undo_redo(
+            abc(),
+            Command::Add(test_item("DD", 20.0, 100.0, 5.0)),
+            &["AA", "BB", "CC", "DD"],
+        );
+    }
+
+    #[test]
+    fn remove_undoes_and_redoes() {
+        let list = abc();
+        let id = list.ids()[1];
+        assert_undo_redo(list, Command::Remove(id), &["AA", "CC"]);
+    }
+
+    #[test]
+    fn edit_undoes_and_redoes() {
+        let list = abc();
+        let id = list.ids()[0];
+        assert_undo_redo(
+            list,
+            Command::Edit(id, test_item("ZZ", 20.0, 100.0, 5.0)),
+            &["ZZ", "BB", "CC"],
+        );
+    }
+
+    #[test]
+    fn move_undoes_and_redoes() {
+        let list = abc();
+        let id = list.ids()[0];
+        assert_undo_redo(list, Command::Move { id, to: 2 }, &["BB", "CC", "AA"]);
+    }
+
+    #[test]
+    fn clear_undoes_and_redoes() {
+        assert_undo_redo(abc(), Command::Clear, &[]);
+    }
+
+    #[test]
+    fn load_undoes_and_redoes() {
+        assert_undo_redo(
+            abc(),
+            Command::Load(vec![
+                test_item("XX", 20.0, 100.0, 5.0),
+                test_item("YY", 20.0, 100.0, 5.0),
+            ]),
+            &["XX", "YY"],
+        );
+    }
+
+    #[test]
+    fn extend_undoes_and_redoes() {
+        assert_undo_redo(
+            abc(),
+            Command::Extend(vec![
+                test_item("XX", 20.0, 100.0, 5.0),
+                test_item("YY", 20.0, 100.0, 5.0),
+            ]),
+            &["AA", "BB", "CC", "XX", "YY"],
+        );
+    }
+
+    #[test]
+    fn undo_keeps_ids() {
+        let mut list = abc();
+        let id = list.ids()[1];
+        list.apply(Command::Remove(id)).unwrap();
+        list.undo();
+        assert_eq!(list.get(id).map(|item| item.name.as_str()), Some("BB"));
+    }
+
+    #[test]
+    fn unknown_ids_are_rejected() {
+        let mut list = abc();
+        let id = list.ids()[0];
+        list.apply(Command::Remove(id)).unwrap();
+        assert_eq!(
+            list.apply(Command::Remove(id)),
+            Err(ListError::UnknownItem(id))
+        );
+        assert_eq!(list.undo.len(), 1);
+    }
+
+    #[test]
+    fn a_new_command_clears_redo() {
+        let mut list = abc();
+        list.apply(Command::Add(test_item("DD", 20.0, 100.0, 5.0)))
+            .unwrap();
+        list.undo();
+        assert!(list.can_redo());
+        list.apply(Command::Add(test_item("EE", 20.0, 100.0, 5.0)))
+            .unwrap();
+        assert!(!list.can_redo());
+        assert!(!list.redo());
+        assert_eq!(names(&list), ["AA", "BB", "CC", "EE"]);
+    }
+
+    #[test]
+    fn moving_in_place_records_nothing() {
+        let mut list = abc();
+        list.apply(Command::Add(test_item("DD", 20.0, 100.0, 5.0)))
+            .unwrap();
+        list.undo();
+        let last = list.ids()[2];
+        list.apply(Command::Move { id: last, to: 5 }).unwrap();
+        assert_eq!(names(&list), ["AA", "BB", "CC"]);
+        assert!(!list.can_undo());
+        assert!(list.can_redo());
+    }
+
+    #[test]
+    fn history_drops_the_oldest_step() {
+        let mut list = ItemList::new();
+        for index in 0..=HISTORY_LIMIT {
+            list.apply(Command::Add(test_item(
+                &format!("Item {}", index),
+                20.0,
+                100.0,
+                5.0,
+            )))
+            .unwrap();
+        }
+        let mut undone = 0;
+        while list.undo() {
+            undone += 1;
+        }
+        assert_eq!(undone, HISTORY_LIMIT);
+        /* The very first Add can no longer be undone. */
+        assert_eq!(names(&list), ["Item 0"]);
+    }
+}
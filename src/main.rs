@@ -1,8 +1,31 @@
 use leptos::mount::mount_to_body;
 use leptos::prelude::*;
+use leptos_router::NavigateOptions;
 use leptos_router::components::{Route, Router, Routes};
+use leptos_router::hooks::{use_navigate, use_query_map};
 use leptos_router::path;
-use protein_comparison_tool::eng::{GroceryItem, UxItem};
+use protein_comparison_tool::amino::{self, AminoAcidProfile, EssentialAminoAcid};
+use protein_comparison_tool::csv_io::{self, CsvColumn, CsvError, ImportOptions};
+use protein_comparison_tool::eng::{
+    self, Field, Grams, GroceryItem, GroceryItemBuilder, Kcal, LabelChecker, Macros, Metric, Money,
+    ParseMoneyError, ProteinQuality, ProteinSource, QualityScale, Servings, SortMode, SortOrder,
+    ValidationIssue,
+};
+use protein_comparison_tool::list::{Command, ItemId, ItemList};
+use protein_comparison_tool::optimizer::{self, Plan, PlanError, PlanRequest, ServingBounds};
+use protein_comparison_tool::pareto::{self, ParetoStatus};
+use protein_comparison_tool::persist;
+use protein_comparison_tool::qr;
+use protein_comparison_tool::scoring::{self, Weights};
+use protein_comparison_tool::sensitivity::{self, RankStability, SensitivityConfig};
+use protein_comparison_tool::session::Session;
+use protein_comparison_tool::share;
+use protein_comparison_tool::storage::{
+    self, KeyValueStore, LocalStorage, SavedLists, StorageError,
+};
+use protein_comparison_tool::units::{
+    Conversion, Package, ServingSize, ServingUnit, UnitError, Weight, WeightUnit,
+};
 
 #[component]
 fn Navbar() -> impl IntoView {
@@ -15,79 +38,717 @@ fn Navbar() -> impl IntoView {
                     <li><a href="https://dan-codes-badly.com/career">Career</a></li>
                     <li><a href="https://dan-codes-badly.com/projects">Projects</a></li>
                     <li><a href="/protein-comparison-tool">Compare Protein</a></li>
+                    <li><a href="/protein-comparison-tool/plan">Budget Plan</a></li>
+                    <li><a href="/protein-comparison-tool/lists">Saved Lists</a></li>
                 </ul>
             </div>
         </div>
     }
 }
 
+const BREAK_EVEN_FIELDS: [Field; 3] = [Field::Cost, Field::Servings, Field::Protein];
+
+/* The list is shared by every route, so it lives above the router. */
+#[derive(Debug, Clone, Copy)]
+struct GroceryList {
+    list: ReadSignal<ItemList>,
+    set_list: WriteSignal<ItemList>,
+    active: ReadSignal<Option<String>>, // the saved list it came from, if any
+    set_active: WriteSignal<Option<String>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct ListRow {
+    id: ItemId,
+    text: String,
+    label_warning: Option<String>,
+    on_frontier: bool,
+    pareto_note: Option<String>,
+    confidence: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Column {
+    ProteinPer100g,
+    KcalPer100g,
+    CostPer100g,
+    CostPerServing,
+    PackageProtein,
+    NetCarbs,
+    CalorieSplit,
+    DigestiblePpd,
+    KcalPerDigestibleGram,
+}
+
+impl Column {
+    const ALL: [Column; 9] = [
+        Column::ProteinPer100g,
+        Column::KcalPer100g,
+        Column::CostPer100g,
+        Column::CostPerServing,
+        Column::PackageProtein,
+        Column::NetCarbs,
+        Column::CalorieSplit,
+        Column::DigestiblePpd,
+        Column::KcalPerDigestibleGram,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            Column::ProteinPer100g => "Protein / 100g",
+            Column::KcalPer100g => "kCal / 100g",
+            Column::CostPer100g => "Cost / 100g",
+            Column::CostPerServing => "Cost / Serving",
+            Column::PackageProtein => "Protein / Package",
+            Column::NetCarbs => "Net Carbs",
+            Column::CalorieSplit => "kCal % P/F/C",
+            Column::DigestiblePpd => "Digestible g / $",
+            Column::KcalPerDigestibleGram => "kCal / Digestible g",
+        }
+    }
+
+    fn render(&self, item: &GroceryItem) -> String {
+        /* Per-100g views need a serving weight, which only package entries have. */
+        let unknown = || "n/a".to_string();
+        match self {
+            Column::ProteinPer100g => item
+                .protein_per_100g()
+                .map_or_else(unknown, |grams| format!("{:.1}g", grams)),
+            Column::KcalPer100g => item
+                .kcal_per_100g()
+                .map_or_else(unknown, |kcal| format!("{:.0}", kcal)),
+            Column::CostPer100g => item
+                .cost_per_100g()
+                .map_or_else(unknown, |cost| format!("${}", cost)),
+            Column::CostPerServing => format!("${}", item.cost_per_serving()),
+            Column::PackageProtein => format!("{:.0}g", item.package_protein()),
+            Column::NetCarbs => item
+                .net_carbs()
+                .map_or_else(unknown, |grams| format!("{:.1}g", grams)),
+            Column::CalorieSplit => {
+                let pct =
+                    |value: Option<f64>| value.map_or("?".to_string(), |v| format!("{:.0}", v));
+                format!(
+                    "{}/{}/{}",
+                    pct(item.pct_calories_from_protein()),
+                    pct(item.pct_calories_from_fat()),
+                    pct(item.pct_calories_from_carbs())
+                )
+            }
+            Column::DigestiblePpd => item
+                .digestible_ppd()
+                .map_or_else(unknown, |ppd| format!("{:.2}g", ppd)),
+            Column::KcalPerDigestibleGram => item
+                .kcal_per_digestible_gram()
+                .map_or_else(unknown, |kcal| format!("{:.2}", kcal)),
+        }
+    }
+}
+
+#[component]
+fn OptionalNumberInput(
+    label: &'static str,
+    id: &'static str,
+    value: ReadSignal<Option<f64>>,
+    set_value: WriteSignal<Option<f64>>,
+) -> impl IntoView {
+    view! {
+        <label for=id>{label}</label>
+        <input type="number" name=id id=id min=0.0 placeholder="(optional)"
+            on:change=move |ev: leptos::ev::Event| {
+                let value_str = event_target_value(&ev);
+                if value_str.trim().is_empty() {
+                    set_value.set(None);
+                } else if let Ok(num) = value_str.parse::<f64>() {
+                    set_value.set(Some(num));
+                }
+            }
+        prop:value=move || value.get().map(|num| num.to_string()).unwrap_or_default()/>
+    }
+}
+
 #[component]
 fn Protein() -> impl IntoView {
     let (name, set_name) = signal("".to_string());
     let (protein, set_protein) = signal(0.0);
     let (calories, set_calories) = signal(0.0);
-    let (cost, set_cost) = signal(0.0);
+    let (cost, set_cost) = signal(Money::default());
+    let (cost_error, set_cost_error) = signal(None::<ParseMoneyError>);
     let (servings, set_servings) = signal(1.0);
-    let (grocery_items, set_grocery_items) = signal(vec![]);
-    let (leanness, set_leanness) = signal(false);
-    let (protein_per_dollar, set_protein_per_dollar) = signal(false);
+    let (fat, set_fat) = signal(None::<f64>);
+    let (carbs, set_carbs) = signal(None::<f64>);
+    let (fiber, set_fiber) = signal(None::<f64>);
+    let (sugars, set_sugars) = signal(None::<f64>);
+    let (added_sugars, set_added_sugars) = signal(None::<f64>);
+    let (sugar_alcohols, set_sugar_alcohols) = signal(None::<f64>);
+    let (quality_source, set_quality_source) = signal(None::<ProteinSource>);
+    let (quality_scale, set_quality_scale) = signal(QualityScale::Pdcaas);
+    let (quality_score, set_quality_score) = signal(None::<f64>);
+    let (amino_acids, set_amino_acids) = signal([None::<f64>; 9]);
+    let entered_profile = move || -> Option<AminoAcidProfile> {
+        /* Only a full profile is used; otherwise the protein source's table profile is. */
+        let values = amino_acids.get();
+        values
+            .iter()
+            .all(Option::is_some)
+            .then(|| AminoAcidProfile(values.map(Option::unwrap_or_default)))
+    };
+    let quality = Memo::new(move |_| -> Option<ProteinQuality> {
+        /* A typed-in score wins over the table value for the chosen source. */
+        match (quality_score.get(), quality_source.get()) {
+            (Some(score), source) => Some(ProteinQuality {
+                scale: quality_scale.get(),
+                score,
+                source,
+            }),
+            (None, Some(source)) => Some(ProteinQuality::from_source(source, quality_scale.get())),
+            (None, None) => None,
+        }
+    });
+    let (use_package, set_use_package) = signal(false);
+    let (package_weight, set_package_weight) = signal(0.0);
+    let (package_unit, set_package_unit) = signal(WeightUnit::Gram);
+    let (serving_size, set_serving_size) = signal(0.0);
+    let (serving_unit, set_serving_unit) = signal(ServingUnit::Gram);
+    let (density, set_density) = signal(1.0);
+    let (scoop_grams, set_scoop_grams) = signal(0.0);
+    let package = Memo::new(move |_| -> Option<Package> {
+        if !use_package.get() {
+            return None;
+        }
+        Some(Package {
+            weight: Weight::new(package_weight.get(), package_unit.get()),
+            serving: ServingSize::new(serving_size.get(), serving_unit.get()),
+            conversion: Conversion {
+                density_g_per_ml: Some(density.get()),
+                scoop_grams: Some(scoop_grams.get()),
+            },
+        })
+    });
+    let package_servings = Memo::new(move |_| -> Option<Result<Servings, UnitError>> {
+        package.get().map(|_package| _package.servings())
+    });
+    let package_error = move || package_servings.get().and_then(|result| result.err());
+    let GroceryList {
+        list,
+        set_list,
+        set_active,
+        ..
+    } = expect_context::<GroceryList>();
+    let grocery_items = Memo::new(move |_| list.read().items());
+    let (editing, set_editing) = signal(None::<ItemId>);
+    let (columns, set_columns) = signal(Vec::<Column>::new());
+    let (label_tolerance, set_label_tolerance) = signal(LabelChecker::default().tolerance_pct);
+    let pareto_analysis = Memo::new(move |_| pareto::pareto_frontier(&grocery_items.read()));
+    let pareto_row = move |index: usize| -> (bool, Option<String>) {
+        /* Only meaningful once there is something to compare against. */
+        let _grocery = grocery_items.read();
+        if _grocery.len() < 2 {
+            return (false, None);
+        }
+        let item = &_grocery[index];
+        let status = pareto_analysis.read().statuses.get(index).copied();
+        match status {
+            Some(ParetoStatus::Frontier) => (true, None),
+            Some(ParetoStatus::Dominated { by }) => {
+                let winner = &_grocery[by];
+                let reasons: Vec<String> =
+                    pareto::advantages(winner, item, &pareto::DEFAULT_METRICS)
+                        .into_iter()
+                        .map(|metric| match metric {
+                            Metric::Leanness => format!(
+                                "leaner ({:.1} vs {:.1} kCal/g)",
+                                winner.leanness, item.leanness
+                            ),
+                            Metric::Ppd => format!(
+                                "more protein per $ ({:.1} vs {:.1} g)",
+                                winner.ppd, item.ppd
+                            ),
+                            other => other.label().to_string(),
+                        })
+                        .collect();
+                (
+                    false,
+                    Some(format!("{} is {}", winner.name, reasons.join(" and "))),
+                )
+            }
+            None => (false, None),
+        }
+    };
+    let (sort_order, set_sort_order) = signal(SortOrder::default());
+    let (weighted, set_weighted) = signal(false);
+    let (weights, set_weights) = signal(Weights::default());
+    let stability = Memo::new(move |_| -> Option<RankStability> {
+        /* Ranks under whichever sort is showing; insertion order has nothing to be unsure of. */
+        let _grocery = grocery_items.read();
+        if _grocery.len() < 2 {
+            return None;
+        }
+        let config = SensitivityConfig::default();
+        if weighted.get() {
+            Some(sensitivity::rank_stability_weighted(
+                &_grocery,
+                &weights.read(),
+                &config,
+            ))
+        } else {
+            /* Rank in the order shown, so "#1" is the top row whichever way the sort runs. */
+            let order = sort_order.get();
+            order.mode.metric().map(|_| {
+                sensitivity::rank_stability_by(&_grocery, &config, |trial| order.sort(trial))
+            })
+        }
+    });
+    let confidence_row = move |index: usize| -> Option<String> {
+        let _stability = stability.read();
+        let _stability = _stability.as_ref()?;
+        let rank = *_stability.current_ranks.get(index)?;
+        let note = format!(
+            "#{} in {:.0}% of trials",
+            rank + 1,
+            _stability.confidence(index) * 100.0
+        );
+        Some(match _stability.most_likely_rank(index) {
+            Some((likely, probability)) if likely != rank => format!(
+                "{}; more often #{} ({:.0}%)",
+                note,
+                likely + 1,
+                probability * 100.0
+            ),
+            _ => note,
+        })
+    };
+    let to_row = move |index: usize, item: &GroceryItem, line: String| -> ListRow {
+        let text = columns.read().iter().fold(line, |line, column| {
+            format!("{} | {}: {}", line, column.label(), column.render(item))
+        });
+        let checker = LabelChecker {
+            tolerance_pct: label_tolerance.get(),
+            ..LabelChecker::default()
+        };
+        let label_warning = checker
+            .check(item)
+            .filter(|check| check.flagged)
+            .map(|check| {
+                format!(
+                    "Label says {:.0} kCal but the macros add up to {:.0} ({:.0}% off)",
+                    check.labelled, check.estimated, check.pct_off
+                )
+            });
+        let (on_frontier, pareto_note) = pareto_row(index);
+        ListRow {
+            id: list.read().entries()[index].id,
+            text,
+            label_warning,
+            on_frontier,
+            pareto_note,
+            confidence: confidence_row(index),
+        }
+    };
+    let (show_issues, set_show_issues) = signal(false);
+    let (csv_text, set_csv_text) = signal(None::<String>);
+    let (csv_mapping, set_csv_mapping) = signal(None::<Vec<Option<CsvColumn>>>);
+    let csv_preview = Memo::new(move |_| {
+        csv_text.get().map(|text| {
+            csv_io::import(
+                &text,
+                &ImportOptions {
+                    mapping: csv_mapping.get(),
+                    has_header: None,
+                },
+            )
+        })
+    });
+    let read_csv = move |input: web_sys::HtmlInputElement| {
+        let Some(file) = input.files().and_then(|files| files.get(0)) else {
+            return;
+        };
+        leptos::task::spawn_local(async move {
+            let text = wasm_bindgen_futures::JsFuture::from(file.text()).await;
+            set_csv_mapping.set(None);
+            set_csv_text.set(text.ok().and_then(|text| text.as_string()));
+        });
+    };
+    let set_csv_column = move |index: usize, column: Option<CsvColumn>| {
+        let mut mapping = match csv_preview.get_untracked() {
+            Some(Ok(preview)) => preview.mapping,
+            Some(Err(CsvError::NoNameColumn { mapping, .. })) => mapping,
+            _ => return,
+        };
+        if mapping.len() <= index {
+            mapping.resize(index + 1, None);
+        }
+        mapping[index] = column;
+        set_csv_mapping.set(Some(mapping));
+    };
+    let csv_mapping_row = move |headers: Vec<String>, mapping: Vec<Option<CsvColumn>>| {
+        mapping.into_iter().enumerate().map(|(index, column)| {
+            let header = headers.get(index).cloned().unwrap_or_else(|| format!("Column {}", index + 1));
+            view! {
+                <label class="column-toggle">{header}" \u{2192} "
+                    <select on:change=move |ev: leptos::ev::Event| {
+                        let value_str = event_target_value(&ev);
+                        set_csv_column(index, CsvColumn::ALL.into_iter().find(|column| column.key() == value_str));
+                    }>
+                        <option value="" selected=column.is_none()>"(skip)"</option>
+                        {CsvColumn::ALL.iter().filter(|option| !option.is_computed()).map(|option| view! {
+                            <option value=option.key() selected=column == Some(*option)>{option.key()}</option>
+                        }).collect_view()}
+                    </select>
+                </label>
+            }
+        }).collect_view()
+    };
+    let (share_link, set_share_link) = signal(None::<Result<String, String>>);
+    let (shared_status, set_shared_status) =
+        signal(None::<Result<(usize, Option<String>), String>>);
+    let query = use_query_map();
+    let navigate = use_navigate();
+    Effect::new(move |_| {
+        /* Opening a shared link: load it once, then drop it from the address bar so a reload keeps later edits. */
+        let Some(code) = query.read().get("list") else {
+            return;
+        };
+        match share::decode(&code) {
+            Ok(shared) => {
+                /* The stored list is saved over as soon as this loads, so park it under Lists first. */
+                let current = list.get_untracked().items();
+                let kept = if current.is_empty() {
+                    Ok(None)
+                } else {
+                    saved_lists().and_then(|mut lists| {
+                        let name = lists.unused_name("Before shared link");
+                        lists.save(&name, &current).map(|_| Some(name))
+                    })
+                };
+                match kept {
+                    Ok(kept) => {
+                        let count = shared.items.len();
+                        _ = set_list.write().apply(Command::Load(shared.items));
+                        set_active.set(None);
+                        set_weighted.set(false);
+                        set_sort_order.set(shared.sort);
+                        set_shared_status.set(Some(Ok((count, kept))));
+                    }
+                    Err(err) => set_shared_status.set(Some(Err(format!(
+                        "Didn't open the shared list because yours couldn't be kept first: {}",
+                        err
+                    )))),
+                }
+            }
+            Err(err) => set_shared_status.set(Some(Err(err.to_string()))),
+        }
+        navigate(
+            "/",
+            NavigateOptions {
+                replace: true,
+                ..Default::default()
+            },
+        );
+    });
+    let make_share_link = move |_| {
+        let link = share::encode(&grocery_items.read(), sort_order.get())
+            .map(|code| {
+                let origin = window().location().origin().unwrap_or_default();
+                format!("{}{}/?list={}", origin, BASE_PATH, code)
+            })
+            .map_err(|err| err.to_string());
+        set_share_link.set(Some(link));
+    };
+    let (notes, set_notes) = signal(String::new());
+    let (session_error, set_session_error) = signal(None::<String>);
+    let current_session = move || Session {
+        items: grocery_items.get(),
+        sort: sort_order.get(),
+        weighted: weighted.get(),
+        weights: weights.get(),
+        notes: notes.get(),
+        ..Session::default()
+    };
+    let read_session = move |input: web_sys::HtmlInputElement| {
+        let Some(file) = input.files().and_then(|files| files.get(0)) else {
+            return;
+        };
+        leptos::task::spawn_local(async move {
+            let text = wasm_bindgen_futures::JsFuture::from(file.text()).await;
+            let text = text
+                .ok()
+                .and_then(|text| text.as_string())
+                .unwrap_or_default();
+            match Session::from_text(&text) {
+                Ok(session) => {
+                    /* One undo step brings the old items back; sort, weights and notes are replaced. */
+                    _ = set_list.write().apply(Command::Load(session.items));
+                    set_sort_order.set(session.sort);
+                    set_weighted.set(session.weighted);
+                    set_weights.set(session.weights);
+                    set_notes.set(session.notes);
+                    set_session_error.set(None);
+                }
+                Err(err) => set_session_error.set(Some(err.to_string())),
+            }
+        });
+    };
+    let (compare_item, set_compare_item) = signal(None::<ItemId>);
+    let (compare_rival, set_compare_rival) = signal(None::<ItemId>);
+    let (compare_metric, set_compare_metric) = signal(Metric::Ppd);
+    let (compare_field, set_compare_field) = signal(Field::Cost);
+    /* Held by id so a delete or reorder can't swap in a different item; until one is
+    picked, or once it is gone, fall back to the first or second row. */
+    let compared = move |picked: Option<ItemId>, fallback: usize| -> Option<ItemId> {
+        let _list = list.read();
+        picked
+            .filter(|id| _list.get(*id).is_some())
+            .or_else(|| _list.entries().get(fallback).map(|entry| entry.id))
+    };
+    let compared_item = move || compared(compare_item.get(), 0);
+    let compared_rival = move || compared(compare_rival.get(), 1);
+    let break_even = move || -> Option<String> {
+        let (item_id, rival_id) = (compared_item()?, compared_rival()?);
+        let _list = list.read();
+        let item = _list.get(item_id)?;
+        let rival = _list.get(rival_id)?;
+        Some(
+            match eng::break_even(item, rival, compare_metric.get(), compare_field.get()) {
+                Ok(result) => format!(
+                    "{} is {} at {:.2}; {} ({})",
+                    item.name,
+                    result.field,
+                    result.current,
+                    result,
+                    if result.wins_now() {
+                        "it already does"
+                    } else {
+                        "it doesn't yet"
+                    }
+                ),
+                Err(err) => err.to_string(),
+            },
+        )
+    };
+    let (portions, set_portions) = signal(Vec::<(ItemId, f64)>::new());
+    let set_portion = move |id: ItemId, servings: Option<f64>| {
+        set_portions.update(|_portions| {
+            _portions.retain(|(other, _)| *other != id);
+            if let Some(servings) = servings {
+                _portions.push((id, servings));
+            }
+        });
+    };
+    let combined_aminos = move || -> Option<String> {
+        /* Portions of items deleted since are dropped rather than pointing at something else. */
+        let _list = list.read();
+        let chosen: Vec<(&GroceryItem, f64)> = portions
+            .read()
+            .iter()
+            .filter_map(|(id, servings)| _list.get(*id).map(|item| (item, *servings)))
+            .collect();
+        if chosen.is_empty() {
+            return None;
+        }
+        Some(match amino::combine(&chosen) {
+            Ok(combined) => {
+                let summary = format!(
+                    "{:.0}g protein, amino acid score {:.2}",
+                    combined.total_protein, combined.score
+                );
+                if combined.is_complete() {
+                    format!("{}: a complete profile", summary)
+                } else {
+                    format!(
+                        "{}: short on {}, held back most by {}",
+                        summary, combined.limiting, chosen[combined.limiting_item].0.name
+                    )
+                }
+            }
+            Err(err) => err.to_string(),
+        })
+    };
+    let builder = Memo::new(move |_| -> GroceryItemBuilder {
+        GroceryItem::builder()
+            .name(name.get())
+            .protein(Grams::new(protein.get()))
+            .calories(Kcal::new(calories.get()))
+            .cost(cost.get())
+            .servings(match package_servings.get() {
+                Some(Ok(_servings)) => _servings,
+                _ => Servings::new(servings.get()),
+            })
+            .serving_grams(
+                package
+                    .get()
+                    .and_then(|_package| _package.serving.to_grams(&_package.conversion).ok()),
+            )
+            .quality(quality.get())
+            .amino_acids(entered_profile())
+            .macros(Macros {
+                fat: fat.get().map(Grams::new),
+                carbs: carbs.get().map(Grams::new),
+                fiber: fiber.get().map(Grams::new),
+                sugars: sugars.get().map(Grams::new),
+                added_sugars: added_sugars.get().map(Grams::new),
+                sugar_alcohols: sugar_alcohols.get().map(Grams::new),
+            })
+    });
+    let issues: Memo<Vec<ValidationIssue>> = Memo::new(move |_| builder.read().validate());
+    let field_error = move |field: Field| {
+        move || {
+            if !show_issues.get() {
+                return None;
+            }
+            let _messages: Vec<String> = issues
+                .read()
+                .iter()
+                .filter(|issue| issue.field() == field)
+                .map(|issue| issue.to_string())
+                .collect();
+            Some(_messages.join(". ")).filter(|message| !message.is_empty())
+        }
+    };
     let ready = Memo::new(move |_| {
-        if name.read().to_string().len() >= 2
-            && protein.get() > 0.0
-            && calories.get() > 0.0
-            && cost.get() > 0.0
-            && servings.get() >= 1.0
-        {
+        if issues.read().is_empty() && cost_error.read().is_none() && package_error().is_none() {
             "Ready to Add \u{2705}".to_string()
         } else {
             "Invalid Values ⚠️".to_string()
         }
     });
-    let sorted_leanness = Memo::new(move |_| {
-        let mut _grocery: Vec<GroceryItem> = grocery_items.get();
-        _grocery.sort_by(|a, b| a.leanness.cmp(&b.leanness));
-        let _sorted: Vec<String> = _grocery
+    let reset_form = move || {
+        set_show_issues.set(false);
+        set_name.set("".to_string());
+        set_protein.set(0.0);
+        set_calories.set(0.0);
+        set_cost.set(Money::default());
+        set_cost_error.set(None);
+        set_servings.set(1.0);
+        set_package_weight.set(0.0);
+        set_serving_size.set(0.0);
+        set_fat.set(None);
+        set_carbs.set(None);
+        set_fiber.set(None);
+        set_sugars.set(None);
+        set_added_sugars.set(None);
+        set_sugar_alcohols.set(None);
+        set_quality_source.set(None);
+        set_quality_score.set(None);
+        set_amino_acids.set([None; 9]);
+        set_editing.set(None);
+    };
+    let load_item = move |id: ItemId| {
+        /* Put an item back in the form; the Add button then saves over it. */
+        let Some(item) = list.read().get(id).cloned() else {
+            return;
+        };
+        reset_form();
+        set_name.set(item.name);
+        set_protein.set(item.protein.value());
+        set_calories.set(item.calories.value());
+        set_cost.set(item.cost);
+        set_use_package.set(false);
+        set_servings.set(item.servings.value());
+        set_fat.set(item.macros.fat.map(|grams| grams.value()));
+        set_carbs.set(item.macros.carbs.map(|grams| grams.value()));
+        set_fiber.set(item.macros.fiber.map(|grams| grams.value()));
+        set_sugars.set(item.macros.sugars.map(|grams| grams.value()));
+        set_added_sugars.set(item.macros.added_sugars.map(|grams| grams.value()));
+        set_sugar_alcohols.set(item.macros.sugar_alcohols.map(|grams| grams.value()));
+        if let Some(_quality) = item.quality {
+            set_quality_source.set(_quality.source);
+            set_quality_scale.set(_quality.scale);
+            /* Only a typed-in score needs restoring; a table score comes back with the source. */
+            let from_table = _quality
+                .source
+                .is_some_and(|source| source.score(_quality.scale) == _quality.score);
+            set_quality_score.set((!from_table).then_some(_quality.score));
+        }
+        if let Some(profile) = item.amino_acids {
+            set_amino_acids.set(profile.0.map(Some));
+        }
+        set_editing.set(Some(id));
+    };
+    let move_item = move |id: ItemId, offset: isize| {
+        let Some(index) = list.read().position(id) else {
+            return;
+        };
+        let to = index.saturating_add_signed(offset);
+        if to != index {
+            _ = set_list.write().apply(Command::Move { id, to });
+        }
+    };
+    let sorted_by_order = Memo::new(move |_| {
+        let _grocery: Vec<GroceryItem> = grocery_items.get();
+        let order = sort_order.get();
+        let _sorted: Vec<ListRow> = order
+            .sort(&_grocery)
             .into_iter()
-            .map(|item| format!("{}: {} kCal / {}g", item.name, item.calories, item.protein))
+            .map(|index| {
+                let item = &_grocery[index];
+                let line = match order.mode {
+                    SortMode::Insertion | SortMode::Name => format!(
+                        "{}: {}(g) {}(kCal) {} servings",
+                        item.name, item.protein, item.calories, item.servings
+                    ),
+                    SortMode::Leanness => format!(
+                        "{}: {} kCal / {}g = {:.2} kCal/g",
+                        item.name, item.calories, item.protein, item.leanness
+                    ),
+                    SortMode::Ppd => format!(
+                        "{}: {}g per $1",
+                        item.name,
+                        (item.ppd.value() * 100.0).round() / 100.0
+                    ),
+                    SortMode::Lpd => format!(
+                        "{}: {:.2} lpd ({:.2} kCal/g, ${} for {:.1} servings)",
+                        item.name, item.lpd, item.leanness, item.cost, item.servings
+                    ),
+                    SortMode::Cost => format!(
+                        "{}: ${} (${} / serving)",
+                        item.name,
+                        item.cost,
+                        item.cost_per_serving()
+                    ),
+                };
+                to_row(index, item, line)
+            })
             .collect();
         _sorted
     });
-
-    let sorted_protein_per_dollar = Memo::new(move |_| {
-        let mut _grocery: Vec<GroceryItem> = grocery_items.get();
-        _grocery.sort_by(|a, b| a.ppd.cmp(&b.ppd));
-        let _sorted: Vec<String> = _grocery
+    let sorted_weighted = Memo::new(move |_| {
+        let _grocery: Vec<GroceryItem> = grocery_items.get();
+        let _sorted: Vec<ListRow> = scoring::rank(&_grocery, &weights.read())
             .into_iter()
-            .map(|item| {
-                format!(
-                    "{}: {}g per $1",
-                    item.name,
-                    (item.ppd * 100.0).round() / 100.0
-                )
+            .map(|(index, score)| {
+                let item = &_grocery[index];
+                let line = format!("{}: score {:.2}", item.name, score);
+                to_row(index, item, line)
             })
             .collect();
         _sorted
     });
-    let sorted_grocery: Memo<Vec<String>> = Memo::new(move |_| {
-        let mut _grocery: Vec<GroceryItem> = grocery_items.get();
-        let mut _sorted: Vec<String>;
-        if leanness.get() {
-            _sorted = sorted_leanness.get();
-        } else if protein_per_dollar.get() {
-            _sorted = sorted_protein_per_dollar.get();
+    let sorted_grocery: Memo<Vec<ListRow>> = Memo::new(move |_| {
+        if weighted.get() {
+            sorted_weighted.get()
         } else {
-            _sorted = _grocery
-                .into_iter()
-                .map(|item| {
-                    format!(
-                        "{}: {}(g) {}(kCal) {} servings",
-                        item.name, item.protein, item.calories, item.servings
-                    )
-                })
-                .collect();
+            sorted_by_order.get()
         }
-        _sorted
     });
+    let sort_status = move || {
+        if weighted.get() {
+            "Sorted by Weighted Score, highest first (higher is better)".to_string()
+        } else {
+            format!("Sorted by {}", sort_order.get().describe())
+        }
+    };
+    let choose_sort = move |mode: SortMode| {
+        /* Picking the current sort again flips it; a new one starts best first. */
+        set_weighted.set(false);
+        set_sort_order.update(|order| {
+            if order.mode == mode {
+                order.descending = !order.descending;
+            } else {
+                *order = mode.best_first();
+            }
+        });
+    };
     view! {
         <div class="main-container">
                 <div class="flex-container">
@@ -110,6 +771,7 @@ fn Protein() -> impl IntoView {
 
                             prop:value=name
                         />
+                        <span class="field-error">{field_error(Field::Name)}</span>
 
                         <label for="protein">"Protein (g)"</label>
                         <input type="number" name="protein" id="protein" required min=0.0
@@ -120,6 +782,7 @@ fn Protein() -> impl IntoView {
                                 }
                             }
                         prop:value=protein/>
+                        <span class="field-error">{field_error(Field::Protein)}</span>
                         <label for="calories">"Calories"</label>
                         <input type="number" name="calories" id="calories" required min=0.0
                             on:change=move |ev: leptos::ev::Event| {
@@ -129,15 +792,174 @@ fn Protein() -> impl IntoView {
                                 }
                             }
                         prop:value=calories/>
+                        <span class="field-error">{field_error(Field::Calories)}</span>
                         <label for="cost">"Cost"</label>
-                        <input type="number" name="cost" id="cost" min=0.0 required
+                        <input type="number" name="cost" id="cost" min=0.0 step=0.01 required
                             on:change=move |ev: leptos::ev::Event| {
                                 let value_str = event_target_value(&ev);
-                                if let Ok(num) = value_str.parse::<f64>() {
-                                    set_cost.set(num);
+                                match value_str.parse::<Money>() {
+                                    Ok(money) => {
+                                        set_cost.set(money);
+                                        set_cost_error.set(None);
+                                    }
+                                    Err(err) => set_cost_error.set(Some(err)),
+                                }
+                            }
+                        prop:value=move || cost.get().to_string()/>
+                        <span class="field-error">{move || cost_error.get().map(|err| err.to_string())}</span>
+                        <span class="field-error">{field_error(Field::Cost)}</span>
+                        <label for="quality-source">"Protein Source "
+                            <div class="tooltip">" \u{24D8}"
+                                <span class="tooltiptext">"Grams of protein aren't equal: PDCAAS / DIAAS scores how much of it your body can use. Pick a source for a typical score, or type one in."</span>
+                            </div>
+                        </label>
+                        <select name="quality-source" id="quality-source"
+                            on:change=move |ev: leptos::ev::Event| {
+                                set_quality_source.set(event_target_value(&ev).parse::<ProteinSource>().ok());
+                            }
+                        >
+                            <option value="" selected=move || quality_source.get().is_none()>"(unknown)"</option>
+                            {ProteinSource::ALL.iter().map(|source| view! {
+                                <option value=source.label() selected=move || quality_source.get() == Some(*source)>{source.label()}</option>
+                            }).collect_view()}
+                        </select>
+                        <select name="quality-scale" id="quality-scale"
+                            on:change=move |ev: leptos::ev::Event| {
+                                if let Ok(scale) = event_target_value(&ev).parse::<QualityScale>() {
+                                    set_quality_scale.set(scale);
                                 }
                             }
-                        prop:value=cost/>
+                        >
+                            <option value="PDCAAS" selected=move || quality_scale.get() == QualityScale::Pdcaas>"PDCAAS"</option>
+                            <option value="DIAAS" selected=move || quality_scale.get() == QualityScale::Diaas>"DIAAS"</option>
+                        </select>
+                        <OptionalNumberInput label="Quality Score" id="quality-score" value=quality_score set_value=set_quality_score/>
+                        <p class="input-status">{move || quality.get().map(|quality| format!("Using {}", quality))}</p>
+                        <span class="field-error">{field_error(Field::Quality)}</span>
+                        <details class="more-nutrition">
+                            <summary>"More Nutrition (optional)"</summary>
+                            <OptionalNumberInput label="Fat (g)" id="fat" value=fat set_value=set_fat/>
+                            <span class="field-error">{field_error(Field::Fat)}</span>
+                            <OptionalNumberInput label="Total Carbs (g)" id="carbs" value=carbs set_value=set_carbs/>
+                            <span class="field-error">{field_error(Field::Carbs)}</span>
+                            <OptionalNumberInput label="Fiber (g)" id="fiber" value=fiber set_value=set_fiber/>
+                            <span class="field-error">{field_error(Field::Fiber)}</span>
+                            <OptionalNumberInput label="Sugars (g)" id="sugars" value=sugars set_value=set_sugars/>
+                            <span class="field-error">{field_error(Field::Sugars)}</span>
+                            <OptionalNumberInput label="Added Sugars (g)" id="added-sugars" value=added_sugars set_value=set_added_sugars/>
+                            <span class="field-error">{field_error(Field::AddedSugars)}</span>
+                            <OptionalNumberInput label="Sugar Alcohols (g)" id="sugar-alcohols" value=sugar_alcohols set_value=set_sugar_alcohols/>
+                            <span class="field-error">{field_error(Field::SugarAlcohols)}</span>
+                        </details>
+                        <details class="amino-acids">
+                            <summary>"Amino Acids (optional)"</summary>
+                            <p class="input-status">{move || match (entered_profile(), quality_source.get()) {
+                                (Some(_), _) => "Using the profile below.".to_string(),
+                                (None, Some(source)) => format!("Fill in all nine to use your own profile; until then the typical {} profile is used.", source.label()),
+                                (None, None) => "Fill in all nine, in mg per g of protein, or pick a protein source.".to_string(),
+                            }}</p>
+                            <input type="button" class="form-button" value="Fill In From Protein Source"
+                                prop:disabled=move || quality_source.get().is_none()
+                                on:click=move |_| {
+                                    if let Some(source) = quality_source.get() {
+                                        set_amino_acids.set(AminoAcidProfile::for_source(source).0.map(Some));
+                                    }
+                                }
+                            />
+                            {EssentialAminoAcid::ALL.into_iter().enumerate().map(|(index, amino_acid)| {
+                                let id = format!("amino-{}", index);
+                                view! {
+                                    <label for=id.clone()>{format!("{} (mg/g)", amino_acid.label())}</label>
+                                    <input type="number" name=id.clone() id=id min=0.0
+                                        placeholder=move || quality_source.get()
+                                            .map(|source| format!("{:.0}", AminoAcidProfile::for_source(source).get(amino_acid)))
+                                            .unwrap_or_else(|| "(optional)".to_string())
+                                        on:change=move |ev: leptos::ev::Event| {
+                                            let value = event_target_value(&ev)
+                                                .trim()
+                                                .parse::<f64>()
+                                                .ok()
+                                                .filter(|num| num.is_finite() && *num >= 0.0);
+                                            set_amino_acids.update(|values| values[index] = value);
+                                        }
+                                    prop:value=move || amino_acids.get()[index].map(|num| num.to_string()).unwrap_or_default()/>
+                                }
+                            }).collect_view()}
+                            <span class="field-error">{field_error(Field::AminoAcids)}</span>
+                        </details>
+                        <label for="use-package">
+                            <input type="checkbox" name="use-package" id="use-package"
+                                on:change=move |ev: leptos::ev::Event| {
+                                    set_use_package.set(event_target_checked(&ev));
+                                }
+                            prop:checked=use_package/>
+                            "Enter package weight instead of servings"
+                        </label>
+                        <Show when=move || use_package.get()>
+                            <label for="package-weight">"Package Weight"</label>
+                            <input type="number" name="package-weight" id="package-weight" min=0.0
+                                on:change=move |ev: leptos::ev::Event| {
+                                    let value_str = event_target_value(&ev);
+                                    if let Ok(num) = value_str.parse::<f64>() {
+                                        set_package_weight.set(num);
+                                    }
+                                }
+                            prop:value=package_weight/>
+                            <select name="package-unit" id="package-unit"
+                                on:change=move |ev: leptos::ev::Event| {
+                                    if let Ok(unit) = event_target_value(&ev).parse::<WeightUnit>() {
+                                        set_package_unit.set(unit);
+                                    }
+                                }
+                            >
+                                {WeightUnit::ALL.iter().map(|unit| view! {
+                                    <option value=unit.to_string() selected=move || package_unit.get() == *unit>{unit.to_string()}</option>
+                                }).collect_view()}
+                            </select>
+                            <label for="serving-size">"Serving Size"</label>
+                            <input type="number" name="serving-size" id="serving-size" min=0.0
+                                on:change=move |ev: leptos::ev::Event| {
+                                    let value_str = event_target_value(&ev);
+                                    if let Ok(num) = value_str.parse::<f64>() {
+                                        set_serving_size.set(num);
+                                    }
+                                }
+                            prop:value=serving_size/>
+                            <span class="field-error">{field_error(Field::ServingGrams)}</span>
+                            <select name="serving-unit" id="serving-unit"
+                                on:change=move |ev: leptos::ev::Event| {
+                                    if let Ok(unit) = event_target_value(&ev).parse::<ServingUnit>() {
+                                        set_serving_unit.set(unit);
+                                    }
+                                }
+                            >
+                                {ServingUnit::ALL.iter().map(|unit| view! {
+                                    <option value=unit.to_string() selected=move || serving_unit.get() == *unit>{unit.to_string()}</option>
+                                }).collect_view()}
+                            </select>
+                            <Show when=move || serving_unit.get() == ServingUnit::Milliliter>
+                                <label for="density">"Density (g/ml)"</label>
+                                <input type="number" name="density" id="density" min=0.0
+                                    on:change=move |ev: leptos::ev::Event| {
+                                        let value_str = event_target_value(&ev);
+                                        if let Ok(num) = value_str.parse::<f64>() {
+                                            set_density.set(num);
+                                        }
+                                    }
+                                prop:value=density/>
+                            </Show>
+                            <Show when=move || serving_unit.get() == ServingUnit::Scoop>
+                                <label for="scoop-grams">"Scoop Weight (g)"</label>
+                                <input type="number" name="scoop-grams" id="scoop-grams" min=0.0
+                                    on:change=move |ev: leptos::ev::Event| {
+                                        let value_str = event_target_value(&ev);
+                                        if let Ok(num) = value_str.parse::<f64>() {
+                                            set_scoop_grams.set(num);
+                                        }
+                                    }
+                                prop:value=scoop_grams/>
+                            </Show>
+                        </Show>
                         <label for="servings">"Servings"</label>
                         <input type="number" name="servings" id="servings" min=0.0 required value="1.0"
                             on:change=move |ev: leptos::ev::Event| {
@@ -146,81 +968,718 @@ fn Protein() -> impl IntoView {
                                     set_servings.set(num);
                                 }
                             }
-
-                        prop:value=servings/>
+                        prop:disabled=use_package
+                        prop:value=move || match package_servings.get() {
+                            Some(Ok(_servings)) => format!("{:.2}", _servings),
+                            _ => servings.get().to_string(),
+                        }/>
+                        <span class="field-error">{move || package_error().map(|err| err.to_string())}</span>
+                        <span class="field-error">{field_error(Field::Servings)}</span>
 
                         <div class="input-form-buttons">
                             <button
 
                                 on:click=move |_| {
-                                    if ready.get() == "Invalid Values ⚠️".to_string() {
-                                        println!("Not OK!");
+                                    if cost_error.read().is_some() || package_error().is_some() {
+                                        return;
                                     }
-                                    else {
-
-                                        let _uxi = UxItem {
-                                            name: name.read().to_string(),
-                                            protein: protein.get(),
-                                            calories: calories.get(),
-                                            cost: cost.get(),
-                                            servings: servings.get(),
-                                        }.to_grocery();
-                                        set_grocery_items.write().push(_uxi);
-                                        set_name.set("".to_string());
-                                        set_protein.set(0.0);
-                                        set_calories.set(0.0);
-                                        set_cost.set(0.0);
-                                        set_servings.set(1.0);
-
+                                    match builder.get().build() {
+                                        Ok(mut item) => {
+                                            let mut _list = set_list.write();
+                                            match editing.get().and_then(|id| _list.get(id).cloned().map(|original| (id, original))) {
+                                                Some((id, original)) => {
+                                                    /* The form has no input for this, so keep what the item had. */
+                                                    item.serving_grams = item.serving_grams.or(original.serving_grams);
+                                                    _ = _list.apply(Command::Edit(id, item));
+                                                }
+                                                /* Also covers an item undone away mid-edit: keep the user's work. */
+                                                None => _ = _list.apply(Command::Add(item)),
+                                            }
+                                            drop(_list);
+                                            reset_form();
+                                        }
+                                        Err(_) => set_show_issues.set(true),
                                     }
                                 }
 
                             >
-                                "Add"
+                                {move || if editing.get().is_some() { "Save" } else { "Add" }}
 
                             </button>
                             <button
 
-                                on:click=move |_| {
-                                    set_name.set("".to_string());
-                                    set_protein.set(0.0);
-                                    set_calories.set(0.0);
-                                    set_cost.set(0.0);
-                                    set_servings.set(1.0);
-                                }
+                                on:click=move |_| reset_form()
                             >
                                 "Clear"
                             </button>
                         </div>
 
+                        <p class="input-status sort-status">{sort_status}</p>
                         <ul class="display-grocery-items">
                                 <For
                                     each=move || sorted_grocery.get()
-                                    key=|item| item.clone()
-                                    children=move |item: String| {
-                                        view! { <li>{format!("{}", item)}</li> }
+                                    key=|row| row.id
+                                    children=move |row: ListRow| {
+                                        /*
+                                        Keyed on the id so a row keeps its element (and focus) when it's edited or moved;
+                                        what it shows follows the latest row for that id.
+                                        */
+                                        let id = row.id;
+                                        let current = Memo::new(move |previous: Option<&ListRow>| {
+                                            sorted_grocery
+                                                .read()
+                                                .iter()
+                                                .find(|latest| latest.id == id)
+                                                .cloned()
+                                                .or_else(|| previous.cloned())
+                                                .unwrap_or_else(|| row.clone())
+                                        });
+                                        view! {
+                                            <li class:frontier=move || current.read().on_frontier class:editing=move || editing.get() == Some(id)>
+                                                {move || current.read().on_frontier.then(|| view! {
+                                                    <span class="badge frontier-badge" title="Nothing else in the list is both leaner and more protein per dollar">"\u{2605} "</span>
+                                                })}
+                                                {move || current.read().text.clone()}
+                                                {move || current.read().label_warning.clone().map(|warning| view! {
+                                                    <span class="badge warning-badge" title=warning>" \u{26A0} label"</span>
+                                                })}
+                                                {move || current.read().confidence.clone().map(|confidence| view! {
+                                                    <span class="badge confidence-badge" title=format!("{}; this is how often the rank held when they were shuffled within that", SensitivityConfig::default().tolerances)>{confidence}</span>
+                                                })}
+                                                {move || current.read().pareto_note.clone().map(|note| view! {
+                                                    <span class="pareto-note">{note}</span>
+                                                })}
+                                                <span class="row-actions">
+                                                    <Show when=move || {
+                                                        /* Up and down only mean list order when the list is shown in that order. */
+                                                        let order = sort_order.get();
+                                                        !weighted.get() && order.mode == SortMode::Insertion && !order.descending
+                                                    }>
+                                                        <button title="Move up" on:click=move |_| move_item(id, -1)>"\u{2191}"</button>
+                                                        <button title="Move down" on:click=move |_| move_item(id, 1)>"\u{2193}"</button>
+                                                    </Show>
+                                                    <button title="Edit" on:click=move |_| load_item(id)>"Edit"</button>
+                                                    <button title="Delete"
+                                                        on:click=move |_| {
+                                                            if editing.get_untracked() == Some(id) {
+                                                                reset_form();
+                                                            }
+                                                            _ = set_list.write().apply(Command::Remove(id));
+                                                        }
+                                                    >"Delete"</button>
+                                                </span>
+                                            </li>
+                                        }
                                     }
                                 />
                         </ul>
+                        <div class="input-form-buttons">
+                            <h3 style="font-style: italic">"Show Columns"</h3>
+                            {Column::ALL.iter().map(|column| {
+                                let column = *column;
+                                view! {
+                                    <label class="column-toggle">
+                                        <input type="checkbox"
+                                            on:change=move |ev: leptos::ev::Event| {
+                                                if event_target_checked(&ev) {
+                                                    set_columns.write().push(column);
+                                                } else {
+                                                    set_columns.write().retain(|c| *c != column);
+                                                }
+                                            }
+                                        prop:checked=move || columns.read().contains(&column)/>
+                                        {column.label()}
+                                    </label>
+                                }
+                            }).collect_view()}
+                        </div>
+                        <div class="input-form-buttons">
+                            <label for="label-tolerance" class="column-toggle">"Label tolerance (%)"
+                                <input type="number" name="label-tolerance" id="label-tolerance" min=0.0
+                                    on:change=move |ev: leptos::ev::Event| {
+                                        let value_str = event_target_value(&ev);
+                                        if let Ok(num) = value_str.parse::<f64>() {
+                                            set_label_tolerance.set(num);
+                                        }
+                                    }
+                                prop:value=label_tolerance/>
+                            </label>
+                        </div>
                         <div class="input-form-buttons">
                             <h3 style="font-style: italic">"Sort By"</h3>
-                            <input type="button" id="sort-leanness" class="form-button" on:click=move |_| {set_leanness.set(true); set_protein_per_dollar.set(true);} value="Leanness"/>
-                            <input type="button" id="sort-protein-per-dollar" class="form-button" on:click=move |_| {set_leanness.set(false); set_protein_per_dollar.set(true);}value="Protein Per Dollar"/>
-                            <input type="button" class="form-button clear-button" on:click=move |_| {set_grocery_items.write().clear()} value="Clear Items"/>
+                            {SortMode::ALL.iter().map(|mode| {
+                                let mode = *mode;
+                                view! {
+                                    <input type="button" id=format!("sort-{}", mode.key()) class="form-button"
+                                        class:active-sort=move || !weighted.get() && sort_order.get().mode == mode
+                                        title=mode.better()
+                                        on:click=move |_| choose_sort(mode)
+                                    value=mode.label()/>
+                                }
+                            }).collect_view()}
+                            <input type="button" id="sort-direction" class="form-button"
+                                on:click=move |_| {
+                                    set_weighted.set(false);
+                                    set_sort_order.update(|order| order.descending = !order.descending);
+                                }
+                            value=move || if sort_order.get().descending { "\u{2193} Descending" } else { "\u{2191} Ascending" }/>
+                            <input type="button" id="sort-weighted" class="form-button" on:click=move |_| set_weighted.set(true) value="Weighted Score"/>
+                            <input type="button" class="form-button clear-button" on:click=move |_| {_ = set_list.write().apply(Command::Clear);} value="Clear Items"/>
+                            <input type="button" class="form-button" on:click=move |_| {set_list.write().undo();} prop:disabled=move || !list.read().can_undo() value="Undo"/>
+                            <input type="button" class="form-button" on:click=move |_| {set_list.write().redo();} prop:disabled=move || !list.read().can_redo() value="Redo"/>
                         </div>
+                        <details class="score-weights">
+                            <summary>"Score Weights"</summary>
+                            <p class="input-status">"Each metric is scaled so the best item in the list gets 1 and the worst 0, then averaged with these weights."</p>
+                            {Metric::ALL.iter().map(|metric| {
+                                let metric = *metric;
+                                view! {
+                                    <label class="weight-slider">
+                                        {metric.label()}
+                                        <input type="range" min=0.0 max=5.0 step=0.5
+                                            on:input=move |ev: leptos::ev::Event| {
+                                                if let Ok(weight) = event_target_value(&ev).parse::<f64>() {
+                                                    set_weights.write().set(metric, weight);
+                                                    set_weighted.set(true);
+                                                }
+                                            }
+                                        prop:value=move || weights.read().get(metric)/>
+                                        {move || format!("{:.1}", weights.read().get(metric))}
+                                    </label>
+                                }
+                            }).collect_view()}
+                        </details>
+                        <details class="csv-io">
+                            <summary>"Import / Export CSV"</summary>
+                            <a class="form-button" download="protein-comparison.csv"
+                                href=move || data_url("text/csv", &csv_io::export(&grocery_items.read()))
+                            >"Download CSV"</a>
+                            <label for="csv-file">" Import from a spreadsheet: "</label>
+                            <input type="file" name="csv-file" id="csv-file" accept=".csv,text/csv"
+                                on:change:target=move |ev| read_csv(ev.target())
+                            />
+                            {move || csv_preview.get().map(|preview| match preview {
+                                Err(err) => {
+                                    let message = err.to_string();
+                                    match err {
+                                        CsvError::NoNameColumn { headers, mapping } => view! {
+                                            <p class="field-error">{message}"; pick which column holds it."</p>
+                                            {csv_mapping_row(headers.unwrap_or_default(), mapping)}
+                                        }.into_any(),
+                                        _ => view! { <p class="field-error">{message}</p> }.into_any(),
+                                    }
+                                }
+                                Ok(preview) => {
+                                    let headers = preview.headers.clone().unwrap_or_default();
+                                    let count = preview.items.len();
+                                    let items = preview.items.clone();
+                                    view! {
+                                        <p class="input-status">{match &preview.headers {
+                                            Some(_) => "Found a header row; check the columns below.",
+                                            None => "No header row; columns are read as name, protein, calories, cost, servings.",
+                                        }}</p>
+                                        {csv_mapping_row(headers, preview.mapping.clone())}
+                                        <ul class="csv-errors">
+                                            {preview.errors.iter().map(|err| view! { <li class="field-error">{err.to_string()}</li> }).collect_view()}
+                                        </ul>
+                                        <input type="button" class="form-button" prop:disabled=count == 0
+                                            on:click=move |_| {
+                                                _ = set_list.write().apply(Command::Extend(items.clone()));
+                                                set_csv_text.set(None);
+                                            }
+                                        value=format!("Add {} Items ({} rows skipped)", count, preview.errors.len())/>
+                                    }.into_any()
+                                }
+                            })}
+                        </details>
+                        {move || shared_status.get().map(|status| match status {
+                            Ok((count, kept)) => {
+                                let message = match kept {
+                                    Some(name) => format!("Opened a shared list of {} items. Yours is saved under Lists as '{}', and Undo brings it back too.", count, name),
+                                    None => format!("Opened a shared list of {} items.", count),
+                                };
+                                view! { <p class="input-status">{message}</p> }.into_any()
+                            }
+                            Err(err) => view! { <p class="field-error">{err}</p> }.into_any(),
+                        })}
+                        <details class="share-link">
+                            <summary>"Share Link"</summary>
+                            <p class="input-status">"The link holds the items and the sort, not quality scores or notes."</p>
+                            <input type="button" class="form-button" on:click=make_share_link value="Make Link & QR Code"/>
+                            {move || share_link.get().map(|link| match link {
+                                Ok(link) => {
+                                    let code = qr::svg(&link);
+                                    view! {
+                                        <input type="text" readonly class="share-url" prop:value=link
+                                            on:focus:target=move |ev| ev.target().select()
+                                        />
+                                        {match code {
+                                            Ok(svg) => view! {
+                                                <div class="share-qr" inner_html=svg.clone()></div>
+                                                <a class="form-button" download="protein-comparison-qr.svg"
+                                                    href=data_url("image/svg+xml", &svg)
+                                                >"Download QR Code (SVG)"</a>
+                                            }.into_any(),
+                                            Err(err) => view! { <p class="field-error">{err.to_string()}</p> }.into_any(),
+                                        }}
+                                    }.into_any()
+                                }
+                                Err(err) => view! { <p class="field-error">{err}</p> }.into_any(),
+                            })}
+                        </details>
+                        <details class="session-io">
+                            <summary>"Session (JSON)"</summary>
+                            <label for="session-notes">"Notes"</label>
+                            <textarea name="session-notes" id="session-notes" rows="3"
+                                prop:value=move || notes.get()
+                                on:input=move |ev| set_notes.set(event_target_value(&ev))
+                            ></textarea>
+                            <a class="form-button" download="protein-comparison.json"
+                                href=move || data_url("application/json", &current_session().to_json())
+                            >"Download JSON"</a>
+                            <a class="form-button" download="protein-comparison.jsonl"
+                                href=move || data_url("application/jsonl", &current_session().to_json_lines())
+                            >"Download JSON Lines"</a>
+                            <label for="session-file">" Open a session (replaces the list): "</label>
+                            <input type="file" name="session-file" id="session-file" accept=".json,.jsonl,application/json"
+                                on:change:target=move |ev| read_session(ev.target())
+                            />
+                            {move || session_error.get().map(|err| view! { <p class="field-error">{err}</p> })}
+                        </details>
+                        <details class="compare-two">
+                            <summary>"Compare Two"</summary>
+                            <p class="input-status">"How much would the first item have to change to tie the second?"</p>
+                            <select name="compare-item" id="compare-item"
+                                on:change=move |ev: leptos::ev::Event| {
+                                    if let Ok(id) = event_target_value(&ev).parse::<u64>() {
+                                        set_compare_item.set(Some(ItemId(id)));
+                                    }
+                                }
+                            >
+                                {move || list.read().entries().iter().map(|entry| {
+                                    let id = entry.id;
+                                    view! {
+                                        <option value=id.0 selected=move || compared_item() == Some(id)>{entry.item.name.clone()}</option>
+                                    }
+                                }).collect_view()}
+                            </select>
+                            " vs "
+                            <select name="compare-rival" id="compare-rival"
+                                on:change=move |ev: leptos::ev::Event| {
+                                    if let Ok(id) = event_target_value(&ev).parse::<u64>() {
+                                        set_compare_rival.set(Some(ItemId(id)));
+                                    }
+                                }
+                            >
+                                {move || list.read().entries().iter().map(|entry| {
+                                    let id = entry.id;
+                                    view! {
+                                        <option value=id.0 selected=move || compared_rival() == Some(id)>{entry.item.name.clone()}</option>
+                                    }
+                                }).collect_view()}
+                            </select>
+                            <select name="compare-metric" id="compare-metric"
+                                on:change=move |ev: leptos::ev::Event| {
+                                    if let Ok(metric) = event_target_value(&ev).parse::<Metric>() {
+                                        set_compare_metric.set(metric);
+                                    }
+                                }
+                            >
+                                {Metric::ALL.iter().map(|metric| view! {
+                                    <option value=metric.key() selected=move || compare_metric.get() == *metric>{metric.label()}</option>
+                                }).collect_view()}
+                            </select>
+                            <select name="compare-field" id="compare-field"
+                                on:change=move |ev: leptos::ev::Event| {
+                                    let value_str = event_target_value(&ev);
+                                    if let Some(field) = BREAK_EVEN_FIELDS.iter().find(|field| field.to_string() == value_str) {
+                                        set_compare_field.set(*field);
+                                    }
+                                }
+                            >
+                                {BREAK_EVEN_FIELDS.iter().map(|field| view! {
+                                    <option value=field.to_string() selected=move || compare_field.get() == *field>{format!("solve for {}", field)}</option>
+                                }).collect_view()}
+                            </select>
+                            <p class="input-status">{break_even}</p>
+                        </details>
+                        <details class="amino-combine">
+                            <summary>"Combine Proteins"</summary>
+                            <p class="input-status">"Servings of each item eaten together; does the mix cover every essential amino acid?"</p>
+                            {move || list.read().entries().iter().map(|entry| {
+                                let id = entry.id;
+                                let input_id = format!("portion-{}", id.0);
+                                view! {
+                                    <label for=input_id.clone()>{entry.item.name.clone()}</label>
+                                    <input type="number" name=input_id.clone() id=input_id min=0.0 step=0.5 placeholder="0"
+                                        on:change=move |ev: leptos::ev::Event| {
+                                            let value_str = event_target_value(&ev);
+                                            if value_str.trim().is_empty() {
+                                                set_portion(id, None);
+                                            } else if let Ok(num) = value_str.parse::<f64>() {
+                                                set_portion(id, Some(num));
+                                            }
+                                        }
+                                    prop:value=move || portions.read().iter().find(|(other, _)| *other == id).map(|(_, num)| num.to_string()).unwrap_or_default()/>
+                                }
+                            }).collect_view()}
+                            <p class="input-status">{combined_aminos}</p>
+                        </details>
                     </div>
                 </div>
             </div>
     }
 }
 
+#[component]
+fn BudgetPlan() -> impl IntoView {
+    let GroceryList { list, .. } = expect_context::<GroceryList>();
+    let _defaults = PlanRequest::default();
+    let (protein_target, set_protein_target) = signal(_defaults.protein_target.value());
+    let (calorie_ceiling, set_calorie_ceiling) = signal(_defaults.calorie_ceiling.value());
+    let (days, set_days) = signal(_defaults.days);
+    /* Held by id so a delete or reorder can't move one item's bounds onto another. */
+    let (bounds, set_bounds) = signal(Vec::<(ItemId, ServingBounds)>::new());
+    /* The names go with the plan, since its lines index the list as it was when it ran. */
+    let (plan, set_plan) = signal(None::<(Vec<String>, Result<Plan, PlanError>)>);
+    let item_bounds = move |id: ItemId| {
+        bounds
+            .read()
+            .iter()
+            .find(|(other, _)| *other == id)
+            .map(|(_, item_bounds)| *item_bounds)
+            .unwrap_or_default()
+    };
+    let set_item_bounds = move |id: ItemId, update: &dyn Fn(&mut ServingBounds)| {
+        let mut item_bounds = item_bounds(id);
+        update(&mut item_bounds);
+        set_bounds.update(|_bounds| {
+            _bounds.retain(|(other, _)| *other != id);
+            _bounds.push((id, item_bounds));
+        });
+    };
+    let run = move || {
+        let (_items, item_ids): (Vec<GroceryItem>, Vec<ItemId>) = list
+            .read()
+            .entries()
+            .iter()
+            .map(|entry| (entry.item.clone(), entry.id))
+            .unzip();
+        let request = PlanRequest {
+            protein_target: Grams::new(protein_target.get()),
+            calorie_ceiling: Kcal::new(calorie_ceiling.get()),
+            days: days.get(),
+            bounds: item_ids.into_iter().map(item_bounds).collect(),
+            ..PlanRequest::default()
+        };
+        let names = _items.iter().map(|item| item.name.clone()).collect();
+        set_plan.set(Some((names, optimizer::optimize(&_items, &request))));
+    };
+
+    view! {
+        <div class="main-container">
+            <div class="flex-container">
+                <h1>"Budget Plan"</h1>
+                <p style="font-style: italic; display: block">"The cheapest whole-package shopping list from your items that hits a daily protein target without going over a daily calorie ceiling."</p>
+                <div class="div-form">
+                    <label for="protein-target">"Protein per day (g)"</label>
+                    <input type="number" name="protein-target" id="protein-target" min=0.0
+                        on:change=move |ev: leptos::ev::Event| {
+                            if let Ok(num) = event_target_value(&ev).parse::<f64>() {
+                                set_protein_target.set(num);
+                            }
+                        }
+                    prop:value=protein_target/>
+                    <label for="calorie-ceiling">"Calories per day (at most)"</label>
+                    <input type="number" name="calorie-ceiling" id="calorie-ceiling" min=0.0
+                        on:change=move |ev: leptos::ev::Event| {
+                            if let Ok(num) = event_target_value(&ev).parse::<f64>() {
+                                set_calorie_ceiling.set(num);
+                            }
+                        }
+                    prop:value=calorie_ceiling/>
+                    <label for="plan-days">"Days"</label>
+                    <input type="number" name="plan-days" id="plan-days" min=1 max=optimizer::MAX_DAYS step=1
+                        on:change=move |ev: leptos::ev::Event| {
+                            if let Ok(num) = event_target_value(&ev).parse::<u32>() {
+                                set_days.set(num);
+                            }
+                        }
+                    prop:value=days/>
+                </div>
+                <table class="plan-bounds">
+                    <tr><th>"Item"</th><th>"Min servings / day"</th><th>"Max servings / day"</th></tr>
+                    {move || list.read().entries().iter().map(|entry| {
+                        let id = entry.id;
+                        view! {
+                            <tr>
+                                <td>{entry.item.name.clone()}</td>
+                                <td><input type="number" min=0 step=1
+                                    on:change=move |ev: leptos::ev::Event| {
+                                        let min = event_target_value(&ev).parse::<u32>().unwrap_or(0);
+                                        set_item_bounds(id, &|_bounds| _bounds.min = min);
+                                    }
+                                prop:value=move || item_bounds(id).min/></td>
+                                <td><input type="number" min=0 step=1 placeholder="(any)"
+                                    on:change=move |ev: leptos::ev::Event| {
+                                        let max = event_target_value(&ev).parse::<u32>().ok();
+                                        set_item_bounds(id, &|_bounds| _bounds.max = max);
+                                    }
+                                prop:value=move || item_bounds(id).max.map(|max| max.to_string()).unwrap_or_default()/></td>
+                            </tr>
+                        }
+                    }).collect_view()}
+                </table>
+                <div class="input-form-buttons">
+                    <input type="button" class="form-button" on:click=move |_| run() value="Find Cheapest Plan"/>
+                </div>
+                {move || plan.get().map(|(names, result)| match result {
+                    Err(err) => view! { <p class="field-error">{err.to_string()}</p> }.into_any(),
+                    Ok(_plan) => {
+                        view! {
+                            <ul class="display-grocery-items">
+                                {_plan.lines.iter().map(|line| {
+                                    let name = names.get(line.index).cloned().unwrap_or_default();
+                                    format!(
+                                        "{}: {} servings/day, {} total - buy {} package(s) for ${}",
+                                        name, line.servings_per_day, line.total_servings, line.packages, line.cost
+                                    )
+                                }).map(|text| view! { <li>{text}</li> }).collect_view()}
+                            </ul>
+                            <p class="input-status">{format!(
+                                "Total ${} for {} day(s): {:.1} g protein and {:.0} kCal per day",
+                                _plan.total_cost, days.get(), _plan.protein_per_day, _plan.calories_per_day
+                            )}</p>
+                            {(!_plan.proven_optimal).then(|| view! {
+                                <p class="field-error">"Search stopped early; this is the best plan found, not necessarily the cheapest."</p>
+                            })}
+                        }.into_any()
+                    }
+                })}
+            </div>
+        </div>
+    }
+}
+
+fn data_url(mime: &str, text: &str) -> String {
+    /* Percent-encode everything but unreserved characters so any text survives in an href. */
+    let mut url = format!("data:{};charset=utf-8,", mime);
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-_.~".contains(&byte) {
+            url.push(byte as char);
+        } else {
+            url.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    url
+}
+
+const BASE_PATH: &str = "/protein-comparison-tool";
+
+fn load_list() -> (Vec<GroceryItem>, Option<String>) {
+    /* The message says why the list starts empty, since the next save replaces what was there. */
+    let Some(mut store) = LocalStorage::open() else {
+        return (vec![], None);
+    };
+    match storage::load_current(&mut store) {
+        Ok(items) => (items, None),
+        Err(err) => {
+            log::warn!("{}", err);
+            (vec![], Some(err.to_string()))
+        }
+    }
+}
+
+fn save_list(items: &[GroceryItem]) {
+    if let Some(mut store) = LocalStorage::open()
+        && let Err(err) = store.set(storage::CURRENT_LIST_KEY, &persist::encode(items))
+    {
+        log::warn!("Couldn't save the list: {}", err);
+    }
+}
+
+fn saved_lists() -> Result<SavedLists<LocalStorage>, StorageError> {
+    LocalStorage::open()
+        .map(SavedLists::new)
+        .ok_or_else(|| StorageError::Backend("this browser has no localStorage".to_string()))
+}
+
+#[component]
+fn Lists() -> impl IntoView {
+    let GroceryList {
+        list,
+        set_list,
+        active,
+        set_active,
+    } = expect_context::<GroceryList>();
+    let (names, set_names) = signal(saved_lists().map(|lists| lists.names()).unwrap_or_default());
+    let (message, set_message) = signal(None::<String>);
+    let (new_name, set_new_name) = signal(active.get_untracked().unwrap_or_default());
+    let (renaming, set_renaming) = signal(None::<(String, String)>);
+    let run =
+        move |action: &dyn Fn(&mut SavedLists<LocalStorage>) -> Result<String, StorageError>| {
+            /* Every action reports back and refreshes the names, success or not. */
+            let result = saved_lists().and_then(|mut lists| {
+                let done = action(&mut lists);
+                set_names.set(lists.names());
+                done
+            });
+            set_message.set(Some(match result {
+                Ok(done) => done,
+                Err(err) => err.to_string(),
+            }));
+        };
+    let save_current = move || {
+        let name = new_name.get().trim().to_string();
+        run(&|lists| {
+            lists.save(&name, &list.read().items())?;
+            set_active.set(Some(name.clone()));
+            Ok(format!("Saved '{}'", name))
+        });
+    };
+    let open = move |name: String| {
+        run(&|lists| {
+            let items = lists.load(&name)?;
+            _ = set_list.write().apply(Command::Load(items));
+            set_active.set(Some(name.clone()));
+            set_new_name.set(name.clone());
+            Ok(format!(
+                "Opened '{}' (Undo on the compare page brings back the previous list)",
+                name
+            ))
+        });
+    };
+    let duplicate = move |name: String| {
+        run(&|lists| {
+            let names = lists.names();
+            let copy = (1..)
+                .map(|n| {
+                    if n == 1 {
+                        format!("{} (copy)", name)
+                    } else {
+                        format!("{} (copy {})", name, n)
+                    }
+                })
+                .find(|candidate| !names.contains(candidate))
+                .unwrap_or_default();
+            lists.duplicate(&name, &copy)?;
+            Ok(format!("Copied '{}' to '{}'", name, copy))
+        });
+    };
+    let rename = move |from: String, to: String| {
+        run(&|lists| {
+            lists.rename(&from, &to)?;
+            if active.get_untracked().as_deref() == Some(from.as_str()) {
+                set_active.set(Some(to.trim().to_string()));
+            }
+            set_renaming.set(None);
+            Ok(format!("Renamed '{}' to '{}'", from, to.trim()))
+        });
+    };
+    let delete = move |name: String| {
+        run(&|lists| {
+            lists.delete(&name)?;
+            if active.get_untracked().as_deref() == Some(name.as_str()) {
+                set_active.set(None);
+            }
+            Ok(format!("Deleted '{}'", name))
+        });
+    };
+
+    view! {
+        <div class="main-container">
+            <div class="flex-container">
+                <h1>"Saved Lists"</h1>
+                <p style="font-style: italic; display: block">"Keep separate comparisons, like a Costco run or this month's protein bars, and switch between them. Lists are saved in this browser."</p>
+                <p class="input-status">{move || match active.get() {
+                    Some(name) => format!("Comparing: '{}' ({} items)", name, list.read().len()),
+                    None => format!("Comparing an unsaved list ({} items)", list.read().len()),
+                }}</p>
+                <div class="input-form-buttons">
+                    <label for="list-name">"Save the current list as "</label>
+                    <input type="text" name="list-name" id="list-name" placeholder="(Costco run)"
+                        on:input:target=move |ev| set_new_name.set(ev.target().value())
+                    prop:value=new_name/>
+                    <input type="button" class="form-button" on:click=move |_| save_current() value="Save"/>
+                </div>
+                <p class="input-status">{message}</p>
+                <ul class="saved-lists">
+                    <For
+                        each=move || names.get()
+                        key=|name| name.clone()
+                        children=move |name: String| {
+                            let row_name = name.clone();
+                            let is_renaming = move || renaming.read().as_ref().is_some_and(|(from, _)| *from == row_name);
+                            let (open_name, duplicate_name, rename_name, delete_name, done_name, title) = (
+                                name.clone(),
+                                name.clone(),
+                                name.clone(),
+                                name.clone(),
+                                name.clone(),
+                                name.clone(),
+                            );
+                            view! {
+                                <li class:active-list=move || active.get().as_deref() == Some(title.as_str())>
+                                    <Show
+                                        when=is_renaming
+                                        fallback=move || view! { <span class="list-name">{name.clone()}</span> }
+                                    >
+                                        <input type="text"
+                                            on:input:target=move |ev| set_renaming.update(|renaming| {
+                                                if let Some((_, to)) = renaming {
+                                                    *to = ev.target().value();
+                                                }
+                                            })
+                                        prop:value=move || renaming.read().as_ref().map(|(_, to)| to.clone()).unwrap_or_default()/>
+                                        <button on:click={
+                                            let from = done_name.clone();
+                                            move |_| {
+                                                let to = renaming.read().as_ref().map(|(_, to)| to.clone()).unwrap_or_default();
+                                                rename(from.clone(), to)
+                                            }
+                                        }>"Done"</button>
+                                    </Show>
+                                    <span class="row-actions">
+                                        <button on:click={let name = open_name.clone(); move |_| open(name.clone())}>"Open"</button>
+                                        <button on:click={let name = rename_name.clone(); move |_| set_renaming.set(Some((name.clone(), name.clone())))}>"Rename"</button>
+                                        <button on:click={let name = duplicate_name.clone(); move |_| duplicate(name.clone())}>"Duplicate"</button>
+                                        <button on:click={let name = delete_name.clone(); move |_| delete(name.clone())}>"Delete"</button>
+                                    </span>
+                                </li>
+                            }
+                        }
+                    />
+                </ul>
+            </div>
+        </div>
+    }
+}
+
 #[component]
 fn App() -> impl IntoView {
+    let (items, unreadable) = load_list();
+    let (list, set_list) = signal(ItemList::from_items(items));
+    let (load_notice, set_load_notice) = signal(unreadable);
+    let (active, set_active) = signal(None::<String>);
+    provide_context(GroceryList {
+        list,
+        set_list,
+        active,
+        set_active,
+    });
+    Effect::new(move |_| save_list(&list.read().items()));
     view! {
-        <Router base="/protein-comparison-tool">
+        <Router base=BASE_PATH>
             <Navbar/>
+            {move || load_notice.get().map(|notice| view! {
+                <p class="field-error load-notice">{notice}" "
+                    <button on:click=move |_| set_load_notice.set(None)>"Dismiss"</button>
+                </p>
+            })}
             <Routes fallback=|| view! {<h1>"WHOOPS! We Couldn't Find That Page"</h1><h3>(404 not found)</h3>}>
                 <Route path=path!("/") view=Protein/>
+                <Route path=path!("/plan") view=BudgetPlan/>
+                <Route path=path!("/lists") view=Lists/>
             </Routes>
         </Router>
     }
@@ -0,0 +1,499 @@
+use crate::eng::GroceryItem;
+use crate::quantity::{Grams, Kcal, Money};
+use std::fmt::Display;
+
+/* Enough for a couple of dozen items in well under a second of wasm. */
+pub const DEFAULT_NODE_LIMIT: u64 = 2_000_000;
+
+/* A year of shopping is already more than one list of prices stays true for. */
+pub const MAX_DAYS: u32 = 366;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ServingBounds {
+    pub min: u32,         // servings per day
+    pub max: Option<u32>, // servings per day; None means "as many as helps"
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanRequest {
+    pub protein_target: Grams, // per day
+    pub calorie_ceiling: Kcal, // per day
+    pub days: u32,
+    pub bounds: Vec<ServingBounds>, // one per item, or empty for no bounds
+    pub node_limit: u64,
+}
+
+impl Default for PlanRequest {
+    fn default() -> Self {
+        PlanRequest {
+            protein_target: Grams::new(150.0),
+            calorie_ceiling: Kcal::new(2000.0),
+            days: 7,
+            bounds: vec![],
+            node_limit: DEFAULT_NODE_LIMIT,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanLine {
+    pub index: usize, // into the optimised items
+    pub servings_per_day: u32,
+    pub total_servings: u64,
+    pub packages: u64,
+    pub cost: Money,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plan {
+    pub lines: Vec<PlanLine>, // only items that are bought
+    pub total_cost: Money,
+    pub protein_per_day: Grams,
+    pub calories_per_day: Kcal,
+    pub proven_optimal: bool, // false if the node limit stopped the search early
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlanError {
+    NoItems,
+    NoDays,
+    TooManyDays(u32),
+    BoundsMismatch { items: usize, bounds: usize },
+    InvalidBounds(usize),
+    Infeasible,
+}
+
+impl Display for PlanError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlanError::NoItems => write!(f, "Add some items to plan with"),
+            PlanError::NoDays => write!(f, "The plan must cover at least one day"),
+            PlanError::TooManyDays(days) => write!(
+                f,
+                "The plan can cover at most {} days, not {}",
+                MAX_DAYS, days
+            ),
+            PlanError::BoundsMismatch { items, bounds } => {
+                write!(
+                    f,
+                    "Got serving limits for {} items but there are {}",
+                    bounds, items
+                )
+            }
+            PlanError::InvalidBounds(index) => {
+                write!(f, "Item {} has a minimum above its maximum", index + 1)
+            }
+            PlanError::Infeasible => {
+                write!(
+                    f,
+                    "No combination hits the protein target under the calorie ceiling"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for PlanError {}
+
+struct Candidate {
+    index: usize,
+    protein: f64,    // g per serving
+    calories: f64,   // kCal per serving
+    cost_cents: i64, // per package
+    servings_per_package: f64,
+    min: u32,
+    max: u32,
+}
+
+fn total_servings(servings_per_day: u32, days: u32) -> u64 {
+    /* Widened before multiplying so a big serving bound over a long plan can't wrap. */
+    (servings_per_day as u64).saturating_mul(days as u64)
+}
+
+impl Candidate {
+    fn packages(&self, servings_per_day: u32, days: u32) -> u64 {
+        /* Whole packages only; the small epsilon keeps 7 x (1/7 package) from becoming 2. */
+        let needed = total_servings(servings_per_day, days) as f64 / self.servings_per_package;
+        (needed - 1e-9).ceil().max(0.0) as u64
+    }
+
+    fn cost(&self, servings_per_day: u32, days: u32) -> i64 {
+        /* An absurd serving bound prices itself out instead of wrapping to a bargain. */
+        i64::try_from(self.packages(servings_per_day, days))
+            .ok()
+            .and_then(|packages| packages.checked_mul(self.cost_cents))
+            .unwrap_or(i64::MAX)
+    }
+
+    fn cents_per_serving_day(&self, days: u32) -> f64 {
+        /* Continuous relaxation of `cost`: never more than the real packaged cost. */
+        self.cost_cents as f64 * days as f64 / self.servings_per_package
+    }
+}
+
+struct Search<'a> {
+    candidates: &'a [Candidate],
+    days: u32,
+    target: f64,
+    ceiling: f64,
+    suffix_min_kcal: Vec<f64>,
+    choice: Vec<u32>,
+    best: Option<(i64, Vec<u32>)>,
+    nodes: u64,
+    node_limit: u64,
+}
+
+impl Search<'_> {
+    fn lower_bound(&self, depth: usize, protein: f64) -> Option<f64> {
+        /*
+        Cheapest fractional way for the remaining items to cover the protein still needed,
+        starting from their minimums. None if even their maximums can't get there.
+        */
+        let rest = &self.candidates[depth..];
+        let mut need = self.target - protein;
+        let mut bound = 0.0;
+        for candidate in rest {
+            bound += candidate.min as f64 * candidate.cents_per_serving_day(self.days);
+            need -= candidate.min as f64 * candidate.protein;
+        }
+        if need <= 0.0 {
+            return Some(bound);
+        }
+        /* `candidates` is sorted by cost per gram, so greedy filling is optimal here. */
+        for candidate in rest.iter().filter(|c| c.protein > 0.0) {
+            let extra = (candidate.max - candidate.min) as f64;
+            let servings = (need / candidate.protein).min(extra);
+            bound += servings * candidate.cents_per_serving_day(self.days);
+            need -= servings * candidate.protein;
+            if need <= 1e-9 {
+                return Some(bound);
+            }
+        }
+        None
+    }
+
+    fn visit(&mut self, depth: usize, protein: f64, calories: f64, cost: i64) {
+        self.nodes += 1;
+        if self.nodes > self.node_limit {
+            return;
+        }
+        if calories + self.suffix_min_kcal[depth] > self.ceiling + 1e-9 {
+            return;
+        }
+        if depth == self.candidates.len() {
+            if protein + 1e-9 >= self.target
+                && self.best.as_ref().is_none_or(|(best, _)| cost < *best)
+            {
+                self.best = Some((cost, self.choice.clone()));
+            }
+            return;
+        }
+        match self.lower_bound(depth, protein) {
+            None => return,
+            Some(bound) => {
+                if let Some((best, _)) = &self.best
+                    && cost as f64 + bound >= *best as f64 - 1e-9
+                {
+                    return;
+                }
+            }
+        }
+
+        let candidate = &self.candidates[depth];
+        for servings in candidate.min..=candidate.max {
+            let next_protein = protein + servings as f64 * candidate.protein;
+            self.choice[depth] = servings;
+            self.visit(
+                depth + 1,
+                next_protein,
+                calories + servings as f64 * candidate.calories,
+                cost.saturating_add(candidate.cost(servings, self.days)),
+            );
+            /* More of this item can't beat the same plan with fewer once the target is met. */
+            if next_protein + 1e-9 >= self.target || self.nodes > self.node_limit {
+                break;
+            }
+        }
+        self.choice[depth] = candidate.min;
+    }
+}
+
+pub fn optimize(items: &[GroceryItem], request: &PlanRequest) -> Result<Plan, PlanError> {
+    /*
+    Picks whole servings per day for each item (the same every day) and buys whole
+    packages to cover them for the horizon, minimising total spend. Exact branch and
+    bound: packages are costed by rounding up, and the bound relaxes that rounding.
+    */
+    if items.is_empty() {
+        return Err(PlanError::NoItems);
+    }
+    if request.days == 0 {
+        return Err(PlanError::NoDays);
+    }
+    if request.days > MAX_DAYS {
+        return Err(PlanError::TooManyDays(request.days));
+    }
+    if !request.bounds.is_empty() && request.bounds.len() != items.len() {
+        return Err(PlanError::BoundsMismatch {
+            items: items.len(),
+            bounds: request.bounds.len(),
+        });
+    }
+
+    let target = request.protein_target.value().max(0.0);
+    let mut candidates = vec![];
+    for (index, item) in items.iter().enumerate() {
+        let bounds = request.bounds.get(index).copied().unwrap_or_default();
+        let protein = item.protein.value().max(0.0);
+        /* Past the point where this item alone covers the target, more never helps. */
+        let useful = if protein > 0.0 {
+            (target / protein).ceil() as u32
+        } else {
+            0
+        };
+        let max = bounds.max.unwrap_or(useful.max(bounds.min));
+        if max < bounds.min {
+            return Err(PlanError::InvalidBounds(index));
+        }
+        if item.servings.value() <= 0.0 {
+            /* Nothing to eat from this package; it can only be left out. */
+            if bounds.min > 0 {
+                return Err(PlanError::Infeasible);
+            }
+            continue;
+        }
+        candidates.push(Candidate {
+            index,
+            protein,
+            calories: item.calories.value().max(0.0),
+            cost_cents: item.cost.cents().max(0),
+            servings_per_package: item.servings.value(),
+            min: bounds.min,
+            max,
+        });
+    }
+    candidates.sort_by(|a, b| {
+        let per_gram = |c: &Candidate| {
+            if c.protein > 0.0 {
+                c.cents_per_serving_day(request.days) / c.protein
+            } else {
+                f64::INFINITY
+            }
+        };
+        per_gram(a).total_cmp(&per_gram(b))
+    });
+
+    let mut suffix_min_kcal = vec![0.0; candidates.len() + 1];
+    for depth in (0..candidates.len()).rev() {
+        let candidate = &candidates[depth];
+        suffix_min_kcal[depth] =
+            suffix_min_kcal[depth + 1] + candidate.min as f64 * candidate.calories;
+    }
+
+    let mut search = Search {
+        candidates: &candidates,
+        days: request.days,
+        target,
+        ceiling: request.calorie_ceiling.value(),
+        suffix_min_kcal,
+        choice: candidates.iter().map(|c| c.min).collect(),
+        best: None,
+        nodes: 0,
+        node_limit: request.node_limit,
+    };
+    search.visit(0, 0.0, 0.0, 0);
+    let proven_optimal = search.nodes <= search.node_limit;
+    let (total_cents, choice) = search.best.ok_or(PlanError::Infeasible)?;
+
+    let mut lines: Vec<PlanLine> = candidates
+        .iter()
+        .zip(choice)
+        .filter(|(_, servings)| *servings > 0)
+        .map(|(candidate, servings)| PlanLine {
+            index: candidate.index,
+            servings_per_day: servings,
+            total_servings: total_servings(servings, request.days),
+            packages: candidate.packages(servings, request.days),
+            cost: Money::from_cents(candidate.cost(servings, request.days)),
+        })
+        .collect();
+    lines.sort_by_key(|line| line.index);
+    let protein_per_day = lines
+        .iter()
+        .map(|line| items[line.index].protein * line.servings_per_day as f64)
+        .fold(Grams::new(0.0), |total, grams| total + grams);
+    let calories_per_day = lines
+        .iter()
+        .map(|line| items[line.index].calories * line.servings_per_day as f64)
+        .fold(Kcal::new(0.0), |total, kcal| total + kcal);
+
+    Ok(Plan {
+        lines,
+        total_cost: Money::from_cents(total_cents),
+        protein_per_day,
+        calories_per_day,
+        proven_optimal,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eng::test_builder;
+    use crate::quantity::Servings;
+
+    fn pantry() -> Vec<GroceryItem> {
+        [
+            ("Chicken", 31.0, 165.0, 12.99, 8.0),
+            ("Eggs", 6.0, 70.0, 4.49, 12.0),
+            ("Whey", 25.0, 120.0, 39.99, 30.0),
+            ("Lentils", 9.0, 115.0, 2.19, 6.0),
+        ]
+        .into_iter()
+        .map(|(name, protein, calories, cost, servings)| {
+            test_builder(name, protein, calories, cost)
+                .servings(Servings::new(servings))
+                .build()
+                .unwrap()
+        })
+        .collect()
+    }
+
+    fn brute_force(items: &[GroceryItem], request: &PlanRequest) -> Option<i64> {
+        /* Every combination of servings within the bounds, costed the same way. */
+        let days = request.days as f64;
+        let mut best = None::<i64>;
+        let mut choice = vec![0u32; items.len()];
+        loop {
+            let protein: f64 = items
+                .iter()
+                .zip(&choice)
+                .map(|(item, &servings)| item.protein.value() * servings as f64)
+                .sum();
+            let calories: f64 = items
+                .iter()
+                .zip(&choice)
+                .map(|(item, &servings)| item.calories.value() * servings as f64)
+                .sum();
+            if protein + 1e-9 >= request.protein_target.value()
+                && calories <= request.calorie_ceiling.value() + 1e-9
+            {
+                let cost = items
+                    .iter()
+                    .zip(&choice)
+                    .map(|(item, &servings)| {
+                        let packages = (servings as f64 * days / item.servings.value() - 1e-9)
+                            .ceil()
+                            .max(0.0);
+                        packages as i64 * item.cost.cents()
+                    })
+                    .sum();
+                best = Some(best.map_or(cost, |best: i64| best.min(cost)));
+            }
+            /* Odometer over the per-item ranges. */
+            let mut position = 0;
+            loop {
+                if position == items.len() {
+                    return best;
+                }
+                let bounds = request.bounds[position];
+                if choice[position] < bounds.max.unwrap() {
+                    choice[position] += 1;
+                    break;
+                }
+                choice[position] = bounds.min;
+                position += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn matches_brute_force_on_small_cases() {
+        let items = pantry();
+        for (target, ceiling, days) in [
+            (60.0, 2000.0, 1),
+            (120.0, 1000.0, 7),
+            (150.0, 2000.0, 3),
+            (45.0, 400.0, 14),
+            (100.0, 700.0, 5),
+        ] {
+            let request = PlanRequest {
+                protein_target: Grams::new(target),
+                calorie_ceiling: Kcal::new(ceiling),
+                days,
+                bounds: vec![
+                    ServingBounds {
+                        min: 0,
+                        max: Some(6)
+                    };
+                    items.len()
+                ],
+                ..Default::default()
+            };
+            let expected = brute_force(&items, &request);
+            let plan = optimize(&items, &request);
+            match expected {
+                Some(cents) => {
+                    let plan = plan.unwrap();
+                    assert!(plan.proven_optimal);
+                    assert_eq!(plan.total_cost, Money::from_cents(cents), "{:?}", request);
+                    assert!(plan.protein_per_day.value() + 1e-9 >= target);
+                    assert!(plan.calories_per_day.value() <= ceiling + 1e-9);
+                }
+                None => assert_eq!(plan, Err(PlanError::Infeasible)),
+            }
+        }
+    }
+
+    #[test]
+    fn infeasible_when_the_ceiling_is_too_low() {
+        let request = PlanRequest {
+            protein_target: Grams::new(150.0),
+            calorie_ceiling: Kcal::new(300.0),
+            ..Default::default()
+        };
+        assert_eq!(optimize(&pantry(), &request), Err(PlanError::Infeasible));
+    }
+
+    #[test]
+    fn zero_days_is_rejected() {
+        let request = PlanRequest {
+            days: 0,
+            ..Default::default()
+        };
+        assert_eq!(optimize(&pantry(), &request), Err(PlanError::NoDays));
+    }
+
+    #[test]
+    fn too_many_days_is_rejected() {
+        let request = PlanRequest {
+            days: MAX_DAYS + 1,
+            ..Default::default()
+        };
+        assert_eq!(
+            optimize(&pantry(), &request),
+            Err(PlanError::TooManyDays(MAX_DAYS + 1))
+        );
+    }
+
+    #[test]
+    fn huge_serving_bounds_do_not_wrap() {
+        let items = pantry()[..1].to_vec();
+        let request = PlanRequest {
+            protein_target: Grams::new(0.0),
+            calorie_ceiling: Kcal::new(f64::MAX),
+            days: MAX_DAYS,
+            bounds: vec![ServingBounds {
+                min: u32::MAX,
+                max: Some(u32::MAX),
+            }],
+            ..Default::default()
+        };
+        let plan = optimize(&items, &request).unwrap();
+        assert_eq!(
+            plan.lines[0].total_servings,
+            u32::MAX as u64 * MAX_DAYS as u64
+        );
+        assert!(plan.total_cost.cents() > 0);
+    }
+}
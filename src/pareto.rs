@@ -0,0 +1,162 @@
+use crate::eng::{GroceryItem, Metric};
+
+/* The trade-off the list is usually read for: lean (lower) versus cheap protein (higher). */
+pub const DEFAULT_METRICS: [Metric; 2] = [Metric::Leanness, Metric::Ppd];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParetoStatus {
+    Frontier,
+    Dominated { by: usize },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParetoAnalysis {
+    pub frontier: Vec<usize>,        // indices into the analysed items
+    pub statuses: Vec<ParetoStatus>, // one per item, in input order
+}
+
+fn better(metric: Metric, a: f64, b: f64) -> bool {
+    if metric.higher_is_better() {
+        a > b
+    } else {
+        a < b
+    }
+}
+
+pub fn dominates(a: &GroceryItem, b: &GroceryItem, metrics: &[Metric]) -> bool {
+    /* At least as good on every metric and strictly better on one. Unknown values never dominate. */
+    let mut strictly_better = false;
+    for metric in metrics {
+        match (metric.value(a), metric.value(b)) {
+            (Some(a_value), Some(b_value)) => {
+                if better(*metric, b_value, a_value) {
+                    return false;
+                }
+                strictly_better |= better(*metric, a_value, b_value);
+            }
+            _ => return false,
+        }
+    }
+    strictly_better
+}
+
+pub fn advantages(winner: &GroceryItem, loser: &GroceryItem, metrics: &[Metric]) -> Vec<Metric> {
+    /* The metrics `winner` is strictly better on, to explain why `loser` is dominated. */
+    metrics
+        .iter()
+        .copied()
+        .filter(|metric| match (metric.value(winner), metric.value(loser)) {
+            (Some(w), Some(l)) => better(*metric, w, l),
+            _ => false,
+        })
+        .collect()
+}
+
+pub fn pareto_frontier_by(items: &[GroceryItem], metrics: &[Metric]) -> ParetoAnalysis {
+    let dominators = |index: usize| {
+        (0..items.len()).filter(move |other| {
+            *other != index && dominates(&items[*other], &items[index], metrics)
+        })
+    };
+    let on_frontier: Vec<bool> = (0..items.len())
+        .map(|index| dominators(index).next().is_none())
+        .collect();
+
+    /* Point a dominated item at a frontier item that beats it, so the reason is actionable. */
+    let statuses: Vec<ParetoStatus> = (0..items.len())
+        .map(|index| {
+            if on_frontier[index] {
+                return ParetoStatus::Frontier;
+            }
+            let by = dominators(index)
+                .find(|other| on_frontier[*other])
+                .or_else(|| dominators(index).next())
+                .unwrap_or(index);
+            ParetoStatus::Dominated { by }
+        })
+        .collect();
+    let frontier = (0..items.len())
+        .filter(|index| on_frontier[*index])
+        .collect();
+
+    ParetoAnalysis { frontier, statuses }
+}
+
+pub fn pareto_frontier(items: &[GroceryItem]) -> ParetoAnalysis {
+    pareto_frontier_by(items, &DEFAULT_METRICS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eng::test_item;
+
+    #[test]
+    fn equal_items_do_not_dominate_each_other() {
+        let a = test_item("Aa", 20.0, 100.0, 5.0);
+        let b = test_item("Bb", 20.0, 100.0, 5.0);
+        assert!(!dominates(&a, &b, &DEFAULT_METRICS));
+        assert!(!dominates(&b, &a, &DEFAULT_METRICS));
+        assert_eq!(pareto_frontier(&[a, b]).frontier, [0, 1]);
+    }
+
+    #[test]
+    fn a_tie_on_one_metric_still_dominates_on_the_other() {
+        let leaner = test_item("Leaner", 25.0, 100.0, 5.0);
+        let same_price = test_item("Same price", 25.0, 150.0, 5.0);
+        assert!(dominates(&leaner, &same_price, &DEFAULT_METRICS));
+        assert!(!dominates(&same_price, &leaner, &DEFAULT_METRICS));
+        assert_eq!(
+            advantages(&leaner, &same_price, &DEFAULT_METRICS),
+            [Metric::Leanness]
+        );
+    }
+
+    #[test]
+    fn each_metric_points_its_own_way() {
+        let lean = test_item("Lean", 30.0, 120.0, 10.0);
+        let fatty = test_item("Fatty", 10.0, 200.0, 2.0);
+        /* Lower leanness is better; higher protein per dollar is better. */
+        assert!(dominates(&lean, &fatty, &[Metric::Leanness]));
+        assert!(!dominates(&fatty, &lean, &[Metric::Leanness]));
+        assert!(dominates(&fatty, &lean, &[Metric::Ppd]));
+        assert!(!dominates(&lean, &fatty, &[Metric::Ppd]));
+        /* Each wins one, so together neither dominates. */
+        assert_eq!(pareto_frontier(&[lean, fatty]).frontier, [0, 1]);
+    }
+
+    #[test]
+    fn dominated_items_point_at_a_frontier_item() {
+        let best = test_item("Best", 30.0, 120.0, 2.0);
+        let middle = test_item("Middle", 25.0, 150.0, 5.0);
+        let worst = test_item("Worst", 10.0, 200.0, 10.0);
+        let analysis = pareto_frontier(&[worst, middle, best]);
+        assert_eq!(analysis.frontier, [2]);
+        assert_eq!(
+            analysis.statuses,
+            [
+                ParetoStatus::Dominated { by: 2 },
+                ParetoStatus::Dominated { by: 2 },
+                ParetoStatus::Frontier,
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_values_never_dominate() {
+        let a = test_item("Aa", 30.0, 120.0, 2.0);
+        let b = test_item("Bb", 10.0, 200.0, 10.0);
+        assert!(!dominates(&a, &b, &[Metric::CostPer100g]));
+    }
+
+    #[test]
+    fn empty_and_single_lists() {
+        let empty = pareto_frontier(&[]);
+        assert!(empty.frontier.is_empty());
+        assert!(empty.statuses.is_empty());
+
+        let single = pareto_frontier(&[test_item("Aa", 20.0, 100.0, 5.0)]);
+        assert_eq!(single.frontier, [0]);
+        assert_eq!(single.statuses, [ParetoStatus::Frontier]);
+    }
+}
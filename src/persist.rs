@@ -0,0 +1,232 @@
+use crate::eng::{GroceryItem, SortOrder};
+use crate::scoring::Weights;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt::Display;
+
+/*
+Stored lists are wrapped in `{ "version": N, "items": [...] }`. Since version 2 the same
+object can also carry the rest of a comparison session (see `session`), so a saved list
+and an exported session file are one format.
+
+To change the format: bump SCHEMA_VERSION and append a step to MIGRATIONS that turns
+version N - 1 into N. New optional item fields don't need a step as long as they are
+`#[serde(default)]`, because older data simply deserializes without them.
+*/
+pub const SCHEMA_VERSION: u32 = 2;
+
+type Migration = fn(Value) -> Result<Value, PersistError>;
+
+/* MIGRATIONS[n] upgrades version n to n + 1. */
+const MIGRATIONS: [Migration; SCHEMA_VERSION as usize] = [wrap_bare_list, add_session_fields];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PersistError {
+    Json(String),
+    UnknownShape,
+    TooNew(u32),
+    InvalidItem { index: usize, message: String },
+}
+
+impl Display for PersistError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PersistError::Json(message) => write!(f, "The saved list isn't valid: {}", message),
+            PersistError::UnknownShape => write!(f, "The saved data isn't a list of items"),
+            PersistError::TooNew(version) => write!(
+                f,
+                "The saved list is version {} but this version only reads up to {}",
+                version, SCHEMA_VERSION
+            ),
+            PersistError::InvalidItem { index, message } => {
+                write!(
+                    f,
+                    "Item {} in the saved list isn't valid: {}",
+                    index + 1,
+                    message
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for PersistError {}
+
+impl From<serde_json::Error> for PersistError {
+    fn from(err: serde_json::Error) -> Self {
+        PersistError::Json(err.to_string())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredList {
+    pub version: u32,
+    pub items: Vec<GroceryItem>,
+}
+
+fn wrap_bare_list(value: Value) -> Result<Value, PersistError> {
+    /* Version 0: a plain JSON array of items, as you'd write by hand. */
+    Ok(serde_json::json!({ "version": 1, "items": value }))
+}
+
+fn add_session_fields(mut value: Value) -> Result<Value, PersistError> {
+    /* Version 1 only had items; spell out the session defaults so the upgraded file is complete. */
+    let object = value.as_object_mut().ok_or(PersistError::UnknownShape)?;
+    object.insert("version".to_string(), Value::from(2));
+    let defaults = [
+        ("sort", serde_json::to_value(SortOrder::default())?),
+        ("weighted", Value::Bool(false)),
+        ("weights", serde_json::to_value(Weights::default())?),
+        ("notes", Value::String(String::new())),
+    ];
+    for (key, default) in defaults {
+        object.entry(key).or_insert(default);
+    }
+    Ok(value)
+}
+
+pub fn stored_version(value: &Value) -> Result<u32, PersistError> {
+    match value {
+        Value::Array(_) => Ok(0),
+        Value::Object(object) => object
+            .get("version")
+            .and_then(Value::as_u64)
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or(PersistError::UnknownShape),
+        _ => Err(PersistError::UnknownShape),
+    }
+}
+
+pub fn migrate(mut value: Value) -> Result<Value, PersistError> {
+    /* Run every step from the stored version up to the current one. */
+    let version = stored_version(&value)?;
+    if version > SCHEMA_VERSION {
+        return Err(PersistError::TooNew(version));
+    }
+    for step in &MIGRATIONS[version as usize..] {
+        value = step(value)?;
+    }
+    Ok(value)
+}
+
+pub fn encode(items: &[GroceryItem]) -> String {
+    let stored = StoredList {
+        version: SCHEMA_VERSION,
+        items: items.to_vec(),
+    };
+    serde_json::to_string(&stored).unwrap_or_default()
+}
+
+pub fn check_item(item: &mut GroceryItem) -> Result<(), String> {
+    /*
+    Never trust stored metrics; they may predate a formula fix or be missing. Nor the
+    values they come from: anything the form would refuse is refused here too.
+    */
+    item.recalculate();
+    let issues = item.validate();
+    if issues.is_empty() {
+        Ok(())
+    } else {
+        Err(issues
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<String>>()
+            .join("; "))
+    }
+}
+
+pub fn check_items(mut items: Vec<GroceryItem>) -> Result<Vec<GroceryItem>, PersistError> {
+    for (index, item) in items.iter_mut().enumerate() {
+        check_item(item).map_err(|message| PersistError::InvalidItem { index, message })?;
+    }
+    Ok(items)
+}
+
+pub fn decode(text: &str) -> Result<Vec<GroceryItem>, PersistError> {
+    let value = migrate(serde_json::from_str(text)?)?;
+    let stored: StoredList = serde_json::from_value(value)?;
+    check_items(stored.items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn whey() -> Value {
+        json!({
+            "protein": 25.0,
+            "calories": 120.0,
+            "cost": { "cents": 3999 },
+            "servings": 30.0,
+            "name": "Whey"
+        })
+    }
+
+    #[test]
+    fn migrates_a_bare_list() {
+        let value = migrate(json!([whey()])).unwrap();
+        assert_eq!(stored_version(&value), Ok(SCHEMA_VERSION));
+        assert_eq!(value["items"], json!([whey()]));
+        assert_eq!(value["weighted"], json!(false));
+        assert_eq!(value["notes"], json!(""));
+        assert_eq!(
+            value["sort"],
+            serde_json::to_value(SortOrder::default()).unwrap()
+        );
+        assert_eq!(
+            value["weights"],
+            serde_json::to_value(Weights::default()).unwrap()
+        );
+    }
+
+    #[test]
+    fn migrates_version_one_and_keeps_its_fields() {
+        let value = migrate(json!({ "version": 1, "items": [whey()], "notes": "mine" })).unwrap();
+        assert_eq!(stored_version(&value), Ok(SCHEMA_VERSION));
+        assert_eq!(value["items"], json!([whey()]));
+        assert_eq!(value["notes"], json!("mine"));
+    }
+
+    #[test]
+    fn decodes_old_versions_with_fresh_metrics() {
+        for text in [
+            json!([whey()]).to_string(),
+            json!({ "version": 1, "items": [whey()] }).to_string(),
+        ] {
+            let items = decode(&text).unwrap();
+            assert_eq!(items.len(), 1);
+            assert_eq!(items[0].name, "Whey");
+            assert!(items[0].ppd.value() > 0.0);
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        let items = decode(&json!([whey()]).to_string()).unwrap();
+        assert_eq!(decode(&encode(&items)), Ok(items));
+    }
+
+    #[test]
+    fn rejects_newer_and_unknown_data() {
+        assert_eq!(
+            decode(&json!({ "version": SCHEMA_VERSION + 1, "items": [] }).to_string()),
+            Err(PersistError::TooNew(SCHEMA_VERSION + 1))
+        );
+        assert_eq!(decode("42"), Err(PersistError::UnknownShape));
+        /* 2^32 would wrap to version 0 if it were truncated. */
+        assert_eq!(
+            decode(&json!({ "version": 4_294_967_296u64, "items": [] }).to_string()),
+            Err(PersistError::UnknownShape)
+        );
+        assert!(matches!(decode("{not json"), Err(PersistError::Json(_))));
+    }
+
+    #[test]
+    fn rejects_items_the_form_would_refuse() {
+        let mut bad = whey();
+        bad["protein"] = json!(-5.0);
+        let err = decode(&json!([whey(), bad]).to_string()).unwrap_err();
+        assert!(matches!(err, PersistError::InvalidItem { index: 1, .. }));
+    }
+}
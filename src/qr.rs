@@ -0,0 +1,72 @@
+use qrcode::render::svg;
+use qrcode::{EcLevel, QrCode};
+use std::fmt::Display;
+
+/* Big enough to scan off a phone held at arm's length; the SVG scales cleanly past that. */
+pub const MIN_SIZE_PX: u32 = 256;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QrError {
+    TooLong(usize),
+    Encode(String),
+}
+
+impl Display for QrError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QrError::TooLong(length) => write!(
+                f,
+                "The link is too long for a QR code ({} characters); remove some items or share the link instead",
+                length
+            ),
+            QrError::Encode(message) => write!(f, "Couldn't make a QR code: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for QrError {}
+
+pub fn svg(text: &str) -> Result<String, QrError> {
+    /*
+    Medium error correction survives screen glare and a cracked phone without pushing a
+    full share link past what fits. Everything is drawn locally; nothing is sent anywhere.
+    */
+    let code = QrCode::with_error_correction_level(text, EcLevel::M).map_err(|err| match err {
+        qrcode::types::QrError::DataTooLong => QrError::TooLong(text.len()),
+        err => QrError::Encode(err.to_string()),
+    })?;
+    Ok(code
+        .render::<svg::Color>()
+        .min_dimensions(MIN_SIZE_PX, MIN_SIZE_PX)
+        .build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::share::MAX_LINK_CHARS;
+
+    fn link(length: usize) -> String {
+        /* Shaped like a real share link: an origin and path, then a base64url payload. */
+        let mut link = "https://example.com/protein-comparison-tool/?list=".to_string();
+        let alphabet = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+        let mut index = 0;
+        while link.len() < length {
+            link.push(alphabet[index % alphabet.len()] as char);
+            index += 7;
+        }
+        link
+    }
+
+    #[test]
+    fn encodes_the_longest_share_link() {
+        let code = svg(&link(MAX_LINK_CHARS)).unwrap();
+        assert!(code.contains("<svg"));
+        assert!(code.trim_end().ends_with("</svg>"));
+    }
+
+    #[test]
+    fn rejects_links_past_qr_capacity() {
+        assert_eq!(svg(&link(3000)), Err(QrError::TooLong(3000)));
+    }
+}
@@ -0,0 +1,233 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum QualityScale {
+    #[default]
+    Pdcaas,
+    Diaas,
+}
+
+impl Display for QualityScale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            QualityScale::Pdcaas => "PDCAAS",
+            QualityScale::Diaas => "DIAAS",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+impl FromStr for QualityScale {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "pdcaas" => Ok(QualityScale::Pdcaas),
+            "diaas" => Ok(QualityScale::Diaas),
+            other => Err(format!("'{}' is not PDCAAS or DIAAS", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ProteinSource {
+    Whey,
+    Casein,
+    Egg,
+    Milk,
+    Beef,
+    Chicken,
+    Soy,
+    Pea,
+    Chickpea,
+    BlackBean,
+    Lentil,
+    Rice,
+    Oat,
+    Wheat,
+    Peanut,
+}
+
+impl ProteinSource {
+    pub const ALL: [ProteinSource; 15] = [
+        ProteinSource::Whey,
+        ProteinSource::Casein,
+        ProteinSource::Egg,
+        ProteinSource::Milk,
+        ProteinSource::Beef,
+        ProteinSource::Chicken,
+        ProteinSource::Soy,
+        ProteinSource::Pea,
+        ProteinSource::Chickpea,
+        ProteinSource::BlackBean,
+        ProteinSource::Lentil,
+        ProteinSource::Rice,
+        ProteinSource::Oat,
+        ProteinSource::Wheat,
+        ProteinSource::Peanut,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ProteinSource::Whey => "whey",
+            ProteinSource::Casein => "casein",
+            ProteinSource::Egg => "egg",
+            ProteinSource::Milk => "milk",
+            ProteinSource::Beef => "beef",
+            ProteinSource::Chicken => "chicken",
+            ProteinSource::Soy => "soy",
+            ProteinSource::Pea => "pea",
+            ProteinSource::Chickpea => "chickpea",
+            ProteinSource::BlackBean => "black bean",
+            ProteinSource::Lentil => "lentil",
+            ProteinSource::Rice => "rice",
+            ProteinSource::Oat => "oat",
+            ProteinSource::Wheat => "wheat",
+            ProteinSource::Peanut => "peanut",
+        }
+    }
+
+    pub fn score(&self, scale: QualityScale) -> f64 {
+        /* Approximate published reference values; (PDCAAS, DIAAS). */
+        let (pdcaas, diaas) = match self {
+            ProteinSource::Whey => (1.00, 1.09),
+            ProteinSource::Casein => (1.00, 1.17),
+            ProteinSource::Egg => (1.00, 1.13),
+            ProteinSource::Milk => (1.00, 1.14),
+            ProteinSource::Beef => (0.92, 1.11),
+            ProteinSource::Chicken => (0.95, 1.08),
+            ProteinSource::Soy => (0.91, 0.90),
+            ProteinSource::Pea => (0.89, 0.82),
+            ProteinSource::Chickpea => (0.78, 0.83),
+            ProteinSource::BlackBean => (0.75, 0.59),
+            ProteinSource::Lentil => (0.52, 0.58),
+            ProteinSource::Rice => (0.47, 0.47),
+            ProteinSource::Oat => (0.57, 0.54),
+            ProteinSource::Wheat => (0.42, 0.45),
+            ProteinSource::Peanut => (0.52, 0.43),
+        };
+        match scale {
+            QualityScale::Pdcaas => pdcaas,
+            QualityScale::Diaas => diaas,
+        }
+    }
+}
+
+impl Display for ProteinSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.label())
+    }
+}
+
+impl FromStr for ProteinSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let wanted = s.trim().to_lowercase();
+        ProteinSource::ALL
+            .into_iter()
+            .find(|source| source.label() == wanted)
+            .ok_or_else(|| format!("'{}' is not a known protein source", s.trim()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ProteinQuality {
+    pub scale: QualityScale,
+    pub score: f64,
+    #[serde(default)]
+    pub source: Option<ProteinSource>, // what the protein is; `score` may be typed in rather than its table value
+}
+
+impl ProteinQuality {
+    pub fn pdcaas(score: f64) -> Self {
+        ProteinQuality {
+            scale: QualityScale::Pdcaas,
+            score,
+            source: None,
+        }
+    }
+
+    pub fn diaas(score: f64) -> Self {
+        ProteinQuality {
+            scale: QualityScale::Diaas,
+            score,
+            source: None,
+        }
+    }
+
+    pub fn from_source(source: ProteinSource, scale: QualityScale) -> Self {
+        ProteinQuality {
+            scale,
+            score: source.score(scale),
+            source: Some(source),
+        }
+    }
+
+    pub fn factor(&self) -> f64 {
+        /* Scores above 1.0 (common for DIAAS) can't make a gram more than fully digestible. */
+        if self.score.is_nan() {
+            0.0
+        } else {
+            self.score.clamp(0.0, 1.0)
+        }
+    }
+}
+
+impl Display for ProteinQuality {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.source {
+            Some(source) => write!(f, "{} {:.2} ({})", self.scale, self.score, source),
+            None => write!(f, "{} {:.2}", self.scale, self.score),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scales_parse_in_any_case() {
+        assert_eq!(" pdcaas ".parse::<QualityScale>(), Ok(QualityScale::Pdcaas));
+        assert_eq!("DIAAS".parse::<QualityScale>(), Ok(QualityScale::Diaas));
+        assert!("bv".parse::<QualityScale>().is_err());
+    }
+
+    #[test]
+    fn sources_parse_from_their_labels() {
+        for source in ProteinSource::ALL {
+            assert_eq!(source.to_string().parse::<ProteinSource>(), Ok(source));
+        }
+        assert_eq!(
+            "Black Bean".parse::<ProteinSource>(),
+            Ok(ProteinSource::BlackBean)
+        );
+        assert_eq!(
+            "tempeh".parse::<ProteinSource>(),
+            Err("'tempeh' is not a known protein source".to_string())
+        );
+    }
+
+    #[test]
+    fn looks_up_the_table_score_for_each_scale() {
+        let whey = ProteinQuality::from_source(ProteinSource::Whey, QualityScale::Diaas);
+        assert_eq!(whey.score, 1.09);
+        assert_eq!(whey.source, Some(ProteinSource::Whey));
+        assert_eq!(ProteinSource::Rice.score(QualityScale::Pdcaas), 0.47);
+        for source in ProteinSource::ALL {
+            for scale in [QualityScale::Pdcaas, QualityScale::Diaas] {
+                assert!((0.0..=1.2).contains(&source.score(scale)));
+            }
+        }
+    }
+
+    #[test]
+    fn factor_caps_at_fully_digestible() {
+        assert_eq!(ProteinQuality::diaas(1.13).factor(), 1.0);
+        assert_eq!(ProteinQuality::pdcaas(0.5).factor(), 0.5);
+        assert_eq!(ProteinQuality::pdcaas(f64::NAN).factor(), 0.0);
+    }
+}
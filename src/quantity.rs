@@ -0,0 +1,381 @@
+use ordered_float::OrderedFloat;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::ops::{Add, Div, Mul, Sub};
+use std::str::FromStr;
+
+macro_rules! quantity {
+    ($name:ident, $unit:expr) => {
+        #[derive(
+            Debug,
+            Clone,
+            Copy,
+            Default,
+            PartialEq,
+            Eq,
+            PartialOrd,
+            Ord,
+            Hash,
+            Serialize,
+            Deserialize,
+        )]
+        #[serde(transparent)]
+        pub struct $name(pub OrderedFloat<f64>);
+
+        impl $name {
+            pub const UNIT: &'static str = $unit;
+
+            pub fn new(value: f64) -> Self {
+                $name(OrderedFloat(value))
+            }
+
+            pub fn value(&self) -> f64 {
+                self.0.0
+            }
+        }
+
+        impl From<$name> for f64 {
+            fn from(quantity: $name) -> Self {
+                quantity.value()
+            }
+        }
+
+        impl Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                /* Forward to f64 so callers keep control of precision, e.g. `{:.2}`. */
+                Display::fmt(&self.value(), f)
+            }
+        }
+    };
+}
+
+macro_rules! additive {
+    ($name:ident) => {
+        impl Add for $name {
+            type Output = $name;
+            fn add(self, rhs: $name) -> $name {
+                $name::new(self.value() + rhs.value())
+            }
+        }
+
+        impl Sub for $name {
+            type Output = $name;
+            fn sub(self, rhs: $name) -> $name {
+                $name::new(self.value() - rhs.value())
+            }
+        }
+
+        impl Mul<f64> for $name {
+            type Output = $name;
+            fn mul(self, rhs: f64) -> $name {
+                $name::new(self.value() * rhs)
+            }
+        }
+    };
+}
+
+macro_rules! ratio {
+    ($lhs:ident / $rhs:ident = $out:ident) => {
+        impl Div<$rhs> for $lhs {
+            type Output = $out;
+            fn div(self, rhs: $rhs) -> $out {
+                $out::new(self.value() / rhs.value())
+            }
+        }
+    };
+}
+
+quantity!(Grams, "g");
+quantity!(Kcal, "kCal");
+quantity!(Servings, "servings");
+quantity!(KcalPerGram, "kCal/g"); // leanness, lower is better
+quantity!(GramsPerDollar, "g/$"); // protein per dollar, higher is better
+quantity!(LeannessPerDollar, "kCal/g per $/serving"); // leanness per dollar
+
+additive!(Grams);
+additive!(Kcal);
+additive!(Servings);
+
+/* Per-serving amounts scale up to the whole package. */
+impl Mul<Servings> for Grams {
+    type Output = Grams;
+    fn mul(self, rhs: Servings) -> Grams {
+        Grams::new(self.value() * rhs.value())
+    }
+}
+
+impl Mul<Servings> for Kcal {
+    type Output = Kcal;
+    fn mul(self, rhs: Servings) -> Kcal {
+        Kcal::new(self.value() * rhs.value())
+    }
+}
+
+ratio!(Kcal / Grams = KcalPerGram);
+ratio!(Grams / Money = GramsPerDollar);
+ratio!(KcalPerGram / Money = LeannessPerDollar);
+
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub struct Money {
+    cents: i64,
+}
+
+/*
+Rounding rules:
+  - f64 -> Money rounds to the nearest cent, halves away from zero (`f64::round`).
+  - Money * f64 and Money / Servings round to the nearest cent, halves to even,
+    so splitting a price over many servings doesn't drift upward.
+  - Per-dollar metrics (`Grams / Money` etc.) divide by the exact cent amount and
+    are never rounded in storage; views round them for display only.
+*/
+impl Money {
+    pub const UNIT: &'static str = "$";
+
+    pub fn new(dollars: f64) -> Self {
+        /* For constants and already-checked values; NaN becomes $0.00 and huge values saturate. */
+        Money::from_cents((dollars * 100.0).round() as i64)
+    }
+
+    pub fn try_new(dollars: f64) -> Result<Self, ParseMoneyError> {
+        /* For amounts that come from input or arithmetic: refuses NaN, infinity and overflow. */
+        let cents = (dollars * 100.0).round();
+        if cents.is_finite() && cents >= i64::MIN as f64 && cents < i64::MAX as f64 {
+            Ok(Money::from_cents(cents as i64))
+        } else {
+            Err(ParseMoneyError::OutOfRange(dollars.to_string()))
+        }
+    }
+
+    pub fn from_cents(cents: i64) -> Self {
+        Money { cents }
+    }
+
+    pub fn cents(&self) -> i64 {
+        self.cents
+    }
+
+    pub fn value(&self) -> f64 {
+        self.cents as f64 / 100.0
+    }
+}
+
+fn round_half_even(value: f64) -> i64 {
+    let rounded = value.round();
+    if (value - value.trunc()).abs() == 0.5 && rounded % 2.0 != 0.0 {
+        (rounded - value.signum()) as i64
+    } else {
+        rounded as i64
+    }
+}
+
+impl From<Money> for f64 {
+    fn from(money: Money) -> Self {
+        money.value()
+    }
+}
+
+impl Display for Money {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        /* Always exactly two decimals so the text parses back to the same cents. */
+        let sign = if self.cents < 0 { "-" } else { "" };
+        let cents = self.cents.unsigned_abs();
+        write!(f, "{}{}.{:02}", sign, cents / 100, cents % 100)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseMoneyError {
+    Empty,
+    Invalid(String),
+    TooManyDecimals(String),
+    OutOfRange(String),
+}
+
+impl Display for ParseMoneyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseMoneyError::Empty => write!(f, "No price entered"),
+            ParseMoneyError::Invalid(text) => write!(f, "'{}' is not a price", text),
+            ParseMoneyError::TooManyDecimals(text) => {
+                write!(f, "'{}' has more than two decimal places", text)
+            }
+            ParseMoneyError::OutOfRange(text) => write!(f, "'{}' is too large for a price", text),
+        }
+    }
+}
+
+impl std::error::Error for ParseMoneyError {}
+
+impl FromStr for Money {
+    type Err = ParseMoneyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        /* Parse digits directly so "3.99" is exactly 399 cents, never 398.99999... */
+        let text = s.trim();
+        let (negative, unsigned) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text),
+        };
+        let unsigned = unsigned.strip_prefix('$').unwrap_or(unsigned);
+        if unsigned.is_empty() {
+            return Err(ParseMoneyError::Empty);
+        }
+
+        let (whole, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+        let all_digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+        if (whole.is_empty() && fraction.is_empty()) || !all_digits(whole) || !all_digits(fraction)
+        {
+            return Err(ParseMoneyError::Invalid(text.to_string()));
+        }
+        if fraction.len() > 2 {
+            return Err(ParseMoneyError::TooManyDecimals(text.to_string()));
+        }
+
+        let dollars: i64 = if whole.is_empty() {
+            0
+        } else {
+            whole
+                .parse()
+                .map_err(|_| ParseMoneyError::OutOfRange(text.to_string()))?
+        };
+        let cents: i64 = format!("{:0<2}", fraction).parse().unwrap_or(0);
+        let total = dollars
+            .checked_mul(100)
+            .and_then(|d| d.checked_add(cents))
+            .ok_or_else(|| ParseMoneyError::OutOfRange(text.to_string()))?;
+        Ok(Money::from_cents(if negative { -total } else { total }))
+    }
+}
+
+/* Arithmetic saturates at the i64 cent range instead of wrapping or panicking. */
+impl Add for Money {
+    type Output = Money;
+    fn add(self, rhs: Money) -> Money {
+        Money::from_cents(self.cents.saturating_add(rhs.cents))
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+    fn sub(self, rhs: Money) -> Money {
+        Money::from_cents(self.cents.saturating_sub(rhs.cents))
+    }
+}
+
+impl Mul<f64> for Money {
+    type Output = Money;
+    fn mul(self, rhs: f64) -> Money {
+        Money::from_cents(round_half_even(self.cents as f64 * rhs))
+    }
+}
+
+impl Mul<i64> for Money {
+    type Output = Money;
+    fn mul(self, rhs: i64) -> Money {
+        Money::from_cents(self.cents.saturating_mul(rhs))
+    }
+}
+
+/* Package price spread over its servings. */
+impl Div<Servings> for Money {
+    type Output = Money;
+    fn div(self, rhs: Servings) -> Money {
+        Money::from_cents(round_half_even(self.cents as f64 / rhs.value()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_exact_cents() {
+        assert_eq!("3.99".parse::<Money>().unwrap().cents(), 399);
+        assert_eq!("0.1".parse::<Money>().unwrap().cents(), 10);
+        assert_eq!("$12".parse::<Money>().unwrap().cents(), 1200);
+        assert_eq!(".5".parse::<Money>().unwrap().cents(), 50);
+        assert_eq!(" 4. ".parse::<Money>().unwrap().cents(), 400);
+    }
+
+    #[test]
+    fn parses_negative_dollar_prefix() {
+        assert_eq!("-$3.50".parse::<Money>().unwrap().cents(), -350);
+        assert_eq!("-2".parse::<Money>().unwrap().cents(), -200);
+    }
+
+    #[test]
+    fn rejects_bad_prices() {
+        assert_eq!("".parse::<Money>(), Err(ParseMoneyError::Empty));
+        assert_eq!("$".parse::<Money>(), Err(ParseMoneyError::Empty));
+        assert_eq!(
+            "3.999".parse::<Money>(),
+            Err(ParseMoneyError::TooManyDecimals("3.999".to_string()))
+        );
+        assert_eq!(
+            "abc".parse::<Money>(),
+            Err(ParseMoneyError::Invalid("abc".to_string()))
+        );
+        assert_eq!(
+            ".".parse::<Money>(),
+            Err(ParseMoneyError::Invalid(".".to_string()))
+        );
+        assert_eq!(
+            "1e3".parse::<Money>(),
+            Err(ParseMoneyError::Invalid("1e3".to_string()))
+        );
+    }
+
+    #[test]
+    fn rejects_overflowing_prices() {
+        /* i64::MAX cents is $92233720368547758.07; one more cent overflows. */
+        assert_eq!(
+            "92233720368547758.07".parse::<Money>(),
+            Ok(Money::from_cents(i64::MAX))
+        );
+        for text in ["99999999999999999999", "92233720368547758.08"] {
+            assert_eq!(
+                text.parse::<Money>(),
+                Err(ParseMoneyError::OutOfRange(text.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for cents in [0, 5, 399, -350, 100_000] {
+            let money = Money::from_cents(cents);
+            assert_eq!(money.to_string().parse::<Money>(), Ok(money));
+        }
+    }
+
+    #[test]
+    fn try_new_refuses_non_finite_and_huge() {
+        assert_eq!(Money::try_new(3.99), Ok(Money::from_cents(399)));
+        assert!(Money::try_new(f64::NAN).is_err());
+        assert!(Money::try_new(f64::INFINITY).is_err());
+        assert!(Money::try_new(1e300).is_err());
+    }
+
+    #[test]
+    fn arithmetic_saturates() {
+        let huge = Money::from_cents(i64::MAX);
+        assert_eq!(huge + Money::new(1.0), huge);
+        let tiny = Money::from_cents(i64::MIN);
+        assert_eq!(tiny - Money::new(1.0), tiny);
+        assert_eq!(Money::new(1e300) + Money::new(1.0), huge);
+        assert_eq!(huge * 2i64, huge);
+    }
+
+    #[test]
+    fn rounds_half_to_even() {
+        assert_eq!(round_half_even(2.5), 2);
+        assert_eq!(round_half_even(3.5), 4);
+        assert_eq!(round_half_even(-2.5), -2);
+        assert_eq!(round_half_even(-3.5), -4);
+        assert_eq!(round_half_even(2.4), 2);
+        assert_eq!(round_half_even(2.6), 3);
+        assert_eq!(round_half_even(0.5), 0);
+    }
+}
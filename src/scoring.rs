@@ -0,0 +1,189 @@
+use crate::eng::{GroceryItem, Metric};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Weights(Vec<(Metric, f64)>);
+
+impl Default for Weights {
+    fn default() -> Self {
+        /* The two questions the list has always answered: how lean, and how cheap. */
+        Weights(vec![(Metric::Leanness, 1.0), (Metric::Ppd, 1.0)])
+    }
+}
+
+impl Weights {
+    pub fn new() -> Self {
+        Weights::default()
+    }
+
+    pub fn get(&self, metric: Metric) -> f64 {
+        self.0
+            .iter()
+            .find(|(m, _)| *m == metric)
+            .map_or(0.0, |(_, weight)| *weight)
+    }
+
+    pub fn set(&mut self, metric: Metric, weight: f64) {
+        /* Negative or NaN weights would reward the wrong direction; treat them as off. */
+        let weight = if weight.is_nan() {
+            0.0
+        } else {
+            weight.max(0.0)
+        };
+        match self.0.iter_mut().find(|(m, _)| *m == metric) {
+            Some(entry) => entry.1 = weight,
+            None => self.0.push((metric, weight)),
+        }
+    }
+
+    pub fn with(mut self, metric: Metric, weight: f64) -> Self {
+        self.set(metric, weight);
+        self
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Metric, f64)> + '_ {
+        self.0.iter().copied().filter(|(_, weight)| *weight > 0.0)
+    }
+}
+
+pub fn normalize(items: &[GroceryItem], metric: Metric) -> Vec<Option<f64>> {
+    /* Min-max scale across the list so 1.0 is the best item on this metric and 0.0 the worst. */
+    let values: Vec<Option<f64>> = items
+        .iter()
+        .map(|item| metric.value(item).filter(|value| value.is_finite()))
+        .collect();
+    let known = values.iter().flatten();
+    let min = known.clone().copied().fold(f64::INFINITY, f64::min);
+    let max = known.copied().fold(f64::NEG_INFINITY, f64::max);
+    let range = max - min;
+
+    values
+        .into_iter()
+        .map(|value| {
+            value.map(|value| {
+                if range <= 0.0 {
+                    1.0
+                } else if metric.higher_is_better() {
+                    (value - min) / range
+                } else {
+                    (max - value) / range
+                }
+            })
+        })
+        .collect()
+}
+
+pub fn score(items: &[GroceryItem], weights: &Weights) -> Vec<f64> {
+    /*
+    Weighted mean of the normalized metrics. An item missing a metric (e.g. no serving
+    weight for per-100g) is scored on the metrics it has rather than counted as worst.
+    */
+    let columns: Vec<(f64, Vec<Option<f64>>)> = weights
+        .iter()
+        .map(|(metric, weight)| (weight, normalize(items, metric)))
+        .collect();
+
+    (0..items.len())
+        .map(|index| {
+            let (total, weight_sum) = columns.iter().fold(
+                (0.0, 0.0),
+                |(total, weight_sum), (weight, column)| match column[index] {
+                    Some(value) => (total + weight * value, weight_sum + weight),
+                    None => (total, weight_sum),
+                },
+            );
+            if weight_sum > 0.0 {
+                total / weight_sum
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
+
+pub fn rank(items: &[GroceryItem], weights: &Weights) -> Vec<(usize, f64)> {
+    /* (index into `items`, score), best first; ties keep insertion order. */
+    let mut ranked: Vec<(usize, f64)> = score(items, weights).into_iter().enumerate().collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eng::test_item;
+
+    #[test]
+    fn equal_values_score_as_best() {
+        let items = vec![
+            test_item("Aa", 20.0, 100.0, 5.0),
+            test_item("Bb", 20.0, 100.0, 5.0),
+        ];
+        assert_eq!(normalize(&items, Metric::Leanness), [Some(1.0), Some(1.0)]);
+        assert_eq!(score(&items, &Weights::default()), [1.0, 1.0]);
+    }
+
+    #[test]
+    fn a_single_item_scores_as_best() {
+        let items = vec![test_item("Aa", 20.0, 100.0, 5.0)];
+        assert_eq!(normalize(&items, Metric::Ppd), [Some(1.0)]);
+        assert_eq!(rank(&items, &Weights::default()), [(0, 1.0)]);
+    }
+
+    #[test]
+    fn lower_is_better_metrics_are_inverted() {
+        let lean = test_item("Lean", 30.0, 120.0, 10.0);
+        let fatty = test_item("Fatty", 10.0, 200.0, 2.0);
+        let items = vec![fatty, lean];
+        assert_eq!(normalize(&items, Metric::Leanness), [Some(0.0), Some(1.0)]);
+        /* Fatty is cheaper protein, so it wins on the higher-is-better metric. */
+        assert_eq!(normalize(&items, Metric::Ppd), [Some(1.0), Some(0.0)]);
+        assert_eq!(normalize(&items, Metric::Cost), [Some(1.0), Some(0.0)]);
+    }
+
+    #[test]
+    fn items_without_a_value_are_left_out() {
+        let items = vec![
+            test_item("Aa", 20.0, 100.0, 5.0),
+            test_item("Bb", 10.0, 100.0, 5.0),
+        ];
+        assert_eq!(normalize(&items, Metric::CostPer100g), [None, None]);
+        let weights = Weights::default().with(Metric::CostPer100g, 5.0);
+        assert_eq!(score(&items, &weights), score(&items, &Weights::default()));
+    }
+
+    #[test]
+    fn zero_weights_score_nothing() {
+        let items = vec![
+            test_item("Aa", 10.0, 100.0, 5.0),
+            test_item("Bb", 20.0, 100.0, 5.0),
+        ];
+        let weights = Weights::new()
+            .with(Metric::Leanness, 0.0)
+            .with(Metric::Ppd, 0.0);
+        assert_eq!(weights.iter().count(), 0);
+        assert_eq!(score(&items, &weights), [0.0, 0.0]);
+        assert_eq!(rank(&items, &weights), [(0, 0.0), (1, 0.0)]);
+    }
+
+    #[test]
+    fn weights_shift_the_ranking() {
+        let lean = test_item("Lean", 30.0, 120.0, 10.0);
+        let fatty = test_item("Fatty", 10.0, 200.0, 2.0);
+        let items = vec![fatty, lean];
+        let leanness_only = Weights::new().with(Metric::Ppd, 0.0);
+        assert_eq!(rank(&items, &leanness_only)[0].0, 1);
+        let ppd_only = Weights::new().with(Metric::Leanness, 0.0);
+        assert_eq!(rank(&items, &ppd_only)[0].0, 0);
+    }
+
+    #[test]
+    fn negative_and_nan_weights_are_off() {
+        let weights = Weights::new()
+            .with(Metric::Leanness, -1.0)
+            .with(Metric::Ppd, f64::NAN);
+        assert_eq!(weights.get(Metric::Leanness), 0.0);
+        assert_eq!(weights.get(Metric::Ppd), 0.0);
+    }
+}
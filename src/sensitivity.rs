@@ -0,0 +1,301 @@
+use crate::eng::{GroceryItem, Metric};
+use crate::scoring::{self, Weights};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tolerances {
+    pub protein_pct: f64,  // +/- percent
+    pub calories_pct: f64, // +/- percent
+    pub cost_pct: f64,     // +/- percent
+}
+
+impl Default for Tolerances {
+    fn default() -> Self {
+        /* Labels may be off by 20%; shelf prices move less than that week to week. */
+        Tolerances {
+            protein_pct: 20.0,
+            calories_pct: 20.0,
+            cost_pct: 10.0,
+        }
+    }
+}
+
+impl std::fmt::Display for Tolerances {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.protein_pct == self.calories_pct {
+            write!(
+                f,
+                "Labels may be {}% off and prices {}%",
+                self.protein_pct, self.cost_pct
+            )
+        } else {
+            write!(
+                f,
+                "Protein may be {}% off, calories {}% and prices {}%",
+                self.protein_pct, self.calories_pct, self.cost_pct
+            )
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SensitivityConfig {
+    pub tolerances: Tolerances,
+    pub trials: u32,
+    pub seed: u64, // same seed, same answer
+}
+
+impl Default for SensitivityConfig {
+    fn default() -> Self {
+        SensitivityConfig {
+            tolerances: Tolerances::default(),
+            trials: 500,
+            seed: 0x5EED,
+        }
+    }
+}
+
+/* SplitMix64: tiny, seedable and identical on every target, which is all this needs. */
+#[derive(Debug, Clone)]
+pub struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        SplitMix64(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    pub fn next_f64(&mut self) -> f64 {
+        /* Uniform in [0, 1) from the top 53 bits. */
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    pub fn jitter(&mut self, pct: f64) -> f64 {
+        /* A multiplier uniform in [1 - pct%, 1 + pct%]. */
+        1.0 + (self.next_f64() * 2.0 - 1.0) * pct / 100.0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RankStability {
+    pub current_ranks: Vec<usize>, // 0 is best, one per item in input order
+    pub probabilities: Vec<Vec<f64>>, // [item][rank], each row sums to 1
+}
+
+impl RankStability {
+    pub fn probability(&self, item: usize, rank: usize) -> f64 {
+        self.probabilities
+            .get(item)
+            .and_then(|ranks| ranks.get(rank))
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    pub fn confidence(&self, item: usize) -> f64 {
+        /* How often the item kept the rank it has with the entered values. */
+        self.current_ranks
+            .get(item)
+            .map_or(0.0, |rank| self.probability(item, *rank))
+    }
+
+    pub fn most_likely_rank(&self, item: usize) -> Option<(usize, f64)> {
+        self.probabilities
+            .get(item)?
+            .iter()
+            .copied()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+    }
+}
+
+pub fn perturb(item: &GroceryItem, protein: f64, calories: f64, cost: f64) -> GroceryItem {
+    /* Scale the noisy inputs and recompute the stored metrics from them. */
+    let mut perturbed = item.clone();
+    perturbed.protein = item.protein * protein;
+    perturbed.calories = item.calories * calories;
+    perturbed.cost = item.cost * cost;
+    perturbed.recalculate();
+    perturbed
+}
+
+pub fn rank_by_metric(items: &[GroceryItem], metric: Metric) -> Vec<usize> {
+    /* Indices best first; items without a value for `metric` go last, in input order. */
+    let mut order: Vec<(usize, Option<f64>)> = items
+        .iter()
+        .enumerate()
+        .map(|(index, item)| (index, metric.value(item)))
+        .collect();
+    order.sort_by(|a, b| match (a.1, b.1) {
+        (Some(a_value), Some(b_value)) if metric.higher_is_better() => b_value.total_cmp(&a_value),
+        (Some(a_value), Some(b_value)) => a_value.total_cmp(&b_value),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+    order.into_iter().map(|(index, _)| index).collect()
+}
+
+fn ranks_of(order: &[usize]) -> Vec<usize> {
+    let mut ranks = vec![0; order.len()];
+    for (rank, index) in order.iter().enumerate() {
+        ranks[*index] = rank;
+    }
+    ranks
+}
+
+pub fn rank_stability_by<F>(
+    items: &[GroceryItem],
+    config: &SensitivityConfig,
+    rank: F,
+) -> RankStability
+where
+    F: Fn(&[GroceryItem]) -> Vec<usize>,
+{
+    /*
+    Deterministic Monte Carlo: every trial jitters each item's protein, calories and cost
+    independently within the tolerances, re-ranks, and tallies where each item landed.
+    */
+    let count = items.len();
+    let current_ranks = ranks_of(&rank(items));
+    let mut tallies = vec![vec![0u32; count]; count];
+    let mut rng = SplitMix64::new(config.seed);
+    let tolerances = config.tolerances;
+    for _ in 0..config.trials {
+        let trial: Vec<GroceryItem> = items
+            .iter()
+            .map(|item| {
+                perturb(
+                    item,
+                    rng.jitter(tolerances.protein_pct),
+                    rng.jitter(tolerances.calories_pct),
+                    rng.jitter(tolerances.cost_pct),
+                )
+            })
+            .collect();
+        for (index, rank) in ranks_of(&rank(&trial)).into_iter().enumerate() {
+            tallies[index][rank] += 1;
+        }
+    }
+
+    let probabilities = if config.trials == 0 {
+        /* Nothing sampled: all that's known is the current order. */
+        current_ranks
+            .iter()
+            .map(|current| {
+                (0..count)
+                    .map(|rank| if rank == *current { 1.0 } else { 0.0 })
+                    .collect()
+            })
+            .collect()
+    } else {
+        tallies
+            .into_iter()
+            .map(|ranks| {
+                ranks
+                    .into_iter()
+                    .map(|tally| tally as f64 / config.trials as f64)
+                    .collect()
+            })
+            .collect()
+    };
+
+    RankStability {
+        current_ranks,
+        probabilities,
+    }
+}
+
+pub fn rank_stability(
+    items: &[GroceryItem],
+    metric: Metric,
+    config: &SensitivityConfig,
+) -> RankStability {
+    rank_stability_by(items, config, |trial| rank_by_metric(trial, metric))
+}
+
+pub fn rank_stability_weighted(
+    items: &[GroceryItem],
+    weights: &Weights,
+    config: &SensitivityConfig,
+) -> RankStability {
+    rank_stability_by(items, config, |trial| {
+        scoring::rank(trial, weights)
+            .into_iter()
+            .map(|(index, _)| index)
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eng::test_item;
+
+    fn items() -> Vec<GroceryItem> {
+        vec![
+            test_item("Chicken", 30.0, 150.0, 10.0),
+            test_item("Tofu", 29.0, 150.0, 10.0),
+            test_item("Beans", 10.0, 150.0, 2.0),
+        ]
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_answer() {
+        let config = SensitivityConfig::default();
+        assert_eq!(
+            rank_stability(&items(), Metric::Ppd, &config),
+            rank_stability(&items(), Metric::Ppd, &config)
+        );
+    }
+
+    #[test]
+    fn probabilities_form_a_distribution() {
+        let stability = rank_stability(&items(), Metric::Ppd, &SensitivityConfig::default());
+        for ranks in &stability.probabilities {
+            assert!(ranks.iter().all(|p| (0.0..=1.0).contains(p)));
+            assert!((ranks.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+        }
+        /* Every trial hands out each rank exactly once. */
+        for rank in 0..items().len() {
+            let total: f64 = (0..items().len())
+                .map(|index| stability.probability(index, rank))
+                .sum();
+            assert!((total - 1.0).abs() < 1e-9);
+        }
+        /* Chicken and tofu are close enough to swap; beans are never caught. */
+        assert!(stability.confidence(0) < 1.0);
+        assert_eq!(stability.current_ranks[2], 0);
+        assert_eq!(stability.confidence(2), 1.0);
+    }
+
+    #[test]
+    fn zero_tolerances_keep_every_rank() {
+        let config = SensitivityConfig {
+            tolerances: Tolerances {
+                protein_pct: 0.0,
+                calories_pct: 0.0,
+                cost_pct: 0.0,
+            },
+            ..SensitivityConfig::default()
+        };
+        let stability = rank_stability(&items(), Metric::Ppd, &config);
+        assert_eq!(stability.current_ranks, [1, 2, 0]);
+        for index in 0..items().len() {
+            assert_eq!(stability.confidence(index), 1.0);
+        }
+    }
+
+    #[test]
+    fn describes_the_tolerances() {
+        assert_eq!(
+            Tolerances::default().to_string(),
+            "Labels may be 20% off and prices 10%"
+        );
+    }
+}
@@ -0,0 +1,189 @@
+use crate::eng::{GroceryItem, SortOrder};
+use crate::persist::{self, PersistError, SCHEMA_VERSION};
+use crate::scoring::Weights;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/*
+A whole comparison session as JSON, in the same versioned format `persist` stores lists in:
+
+    {
+      "version": 2,
+      "items": [GroceryItem, ...],
+      "sort": { "mode": "insertion" | "leanness" | "ppd" | "lpd" | "cost" | "name",
+                "descending": false },
+      "weighted": false,               // rank by `weights` instead of `sort`
+      "weights": [["leanness", 1.0], ["ppd", 1.0]],
+      "notes": "free text"
+    }
+
+An item needs only `name`, `protein` (g per serving), `calories` (kcal per serving),
+`cost` (`{ "cents": 399 }`) and `servings`; everything else is optional and the computed
+metrics (`leanness`, `ppd`, `lpd`) are recalculated on load whatever the file says.
+Metric names in `weights` are `Metric::key()`. A bare array of items and version 1
+files (`{ "version": 1, "items": [...] }`) are upgraded when read.
+
+JSON Lines puts the session without `items` on the first line and one item per line
+after it. Lines without that header are read as a bare list of items.
+*/
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    pub version: u32,
+    pub items: Vec<GroceryItem>,
+    #[serde(default)]
+    pub sort: SortOrder,
+    #[serde(default)]
+    pub weighted: bool,
+    #[serde(default)]
+    pub weights: Weights,
+    #[serde(default)]
+    pub notes: String,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Session {
+            version: SCHEMA_VERSION,
+            items: vec![],
+            sort: SortOrder::default(),
+            weighted: false,
+            weights: Weights::default(),
+            notes: String::new(),
+        }
+    }
+}
+
+impl Session {
+    pub fn from_items(items: Vec<GroceryItem>) -> Self {
+        Session {
+            items,
+            ..Session::default()
+        }
+    }
+
+    fn from_value(value: Value) -> Result<Self, PersistError> {
+        let mut session: Session = serde_json::from_value(persist::migrate(value)?)?;
+        session.version = SCHEMA_VERSION;
+        session.items = persist::check_items(session.items)?;
+        Ok(session)
+    }
+
+    pub fn to_json(&self) -> String {
+        /* Pretty-printed: these files get read and diffed by people too. */
+        let session = Session {
+            version: SCHEMA_VERSION,
+            ..self.clone()
+        };
+        serde_json::to_string_pretty(&session).unwrap_or_default()
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, PersistError> {
+        Session::from_value(serde_json::from_str(text)?)
+    }
+
+    pub fn to_json_lines(&self) -> String {
+        let mut header = serde_json::to_value(Session {
+            version: SCHEMA_VERSION,
+            items: vec![],
+            ..self.clone()
+        })
+        .unwrap_or_default();
+        if let Some(object) = header.as_object_mut() {
+            object.remove("items");
+        }
+        let mut lines = vec![header.to_string()];
+        lines.extend(
+            self.items
+                .iter()
+                .map(|item| serde_json::to_string(item).unwrap_or_default()),
+        );
+        lines.join("\n") + "\n"
+    }
+
+    pub fn from_json_lines(text: &str) -> Result<Self, PersistError> {
+        let mut values = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let value: Value = serde_json::from_str(line)
+                .map_err(|err| PersistError::Json(format!("line {}: {}", index + 1, err)))?;
+            values.push(value);
+        }
+        /* Items never have a `version`, so a first line with one is the header. */
+        let has_header = values
+            .first()
+            .and_then(Value::as_object)
+            .is_some_and(|object| object.contains_key("version"));
+        let value = if has_header {
+            let mut header = values.remove(0);
+            if let Some(object) = header.as_object_mut() {
+                object.insert("items".to_string(), Value::Array(values));
+            }
+            header
+        } else {
+            Value::Array(values)
+        };
+        Session::from_value(value)
+    }
+
+    pub fn from_text(text: &str) -> Result<Self, PersistError> {
+        /* For uploads where the extension can't be trusted: a whole JSON document first, then JSON Lines. */
+        match serde_json::from_str::<Value>(text) {
+            Ok(value) if value.is_array() || value.get("version").is_some() => {
+                Session::from_value(value)
+            }
+            _ => Session::from_json_lines(text),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eng::{SortMode, test_item};
+
+    fn session() -> Session {
+        let item = test_item("Chicken", 31.0, 165.0, 12.99);
+        Session {
+            sort: SortMode::Ppd.best_first(),
+            notes: "Costco prices".to_string(),
+            ..Session::from_items(vec![item])
+        }
+    }
+
+    #[test]
+    fn round_trips_as_json_and_json_lines() {
+        let session = session();
+        assert_eq!(Session::from_json(&session.to_json()), Ok(session.clone()));
+        assert_eq!(
+            Session::from_json_lines(&session.to_json_lines()),
+            Ok(session.clone())
+        );
+        assert_eq!(Session::from_text(&session.to_json_lines()), Ok(session));
+    }
+
+    #[test]
+    fn rejects_invalid_items() {
+        let text = r#"[
+            {"name": "Chicken", "protein": 31, "calories": 165, "cost": {"cents": 1299}, "servings": 8},
+            {"name": "", "protein": 31, "calories": 165, "cost": {"cents": 1299}, "servings": 8}
+        ]"#;
+        assert!(matches!(
+            Session::from_json(text),
+            Err(PersistError::InvalidItem { index: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_invalid_items_in_json_lines() {
+        let mut text = session().to_json_lines();
+        text.push_str(
+            r#"{"name": "Eggs", "protein": 6, "calories": 70, "cost": {"cents": -449}, "servings": 12}"#,
+        );
+        assert!(matches!(
+            Session::from_json_lines(&text),
+            Err(PersistError::InvalidItem { index: 1, .. })
+        ));
+    }
+}
@@ -0,0 +1,429 @@
+use crate::eng::{Grams, GroceryItem, Kcal, Macros, Money, Servings, SortMode, SortOrder};
+use crate::persist;
+use std::fmt::Display;
+
+/*
+The list packed into a link: a small versioned binary format, then unpadded base64url so it
+can sit in a query parameter as-is.
+
+    version: u8 = 1
+    sort mode: u8, index into SortMode::ALL
+    flags: u8, bit 0 = descending
+    item count: varint
+    per item:
+        name: varint byte length, then UTF-8
+        present: u8, bit 0 = serving grams, bits 1..=6 = fat, carbs, fiber, sugars,
+                 added sugars, sugar alcohols
+        protein, calories, servings: zigzag varint, hundredths
+        cost: zigzag varint, cents
+        each present optional field: zigzag varint, hundredths
+
+Quality scores and amino acid profiles stay behind; a session file carries those. Values
+are rounded to the hundredth on the way in, so 28.125 servings opens as 28.13; nothing on a
+label is that precise.
+*/
+pub const SHARE_VERSION: u8 = 1;
+
+/* Long URLs get cut off by chat apps and some browsers well before the spec limits. */
+pub const MAX_LINK_CHARS: usize = 2000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShareError {
+    TooLarge { length: usize, limit: usize },
+    Base64,
+    Truncated,
+    UnsupportedVersion(u8),
+    UnknownSort(u8),
+    BadName,
+    TrailingBytes,
+    InvalidItem { index: usize, message: String },
+}
+
+impl Display for ShareError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShareError::TooLarge { length, limit } => write!(
+                f,
+                "The list is too big to share as a link ({} characters, the limit is {}); export a session file instead",
+                length, limit
+            ),
+            ShareError::Base64 => write!(
+                f,
+                "The shared link is damaged: it has characters a link can't contain"
+            ),
+            ShareError::Truncated => {
+                write!(f, "The shared link is cut short; try copying all of it")
+            }
+            ShareError::UnsupportedVersion(version) => write!(
+                f,
+                "The shared link is format {} but this version only reads format {}",
+                version, SHARE_VERSION
+            ),
+            ShareError::UnknownSort(_) => write!(f, "The shared link is damaged: unknown sort"),
+            ShareError::BadName => write!(f, "The shared link is damaged: an item name isn't text"),
+            ShareError::TrailingBytes => write!(
+                f,
+                "The shared link is damaged: it has extra data at the end"
+            ),
+            ShareError::InvalidItem { index, message } => write!(
+                f,
+                "The shared link is damaged: item {} isn't valid: {}",
+                index + 1,
+                message
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ShareError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SharedList {
+    pub items: Vec<GroceryItem>,
+    pub sort: SortOrder,
+}
+
+const BASE64URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+pub fn base64url_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let bits = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        /* n input bytes make n + 1 output characters; no padding. */
+        for position in 0..=chunk.len() {
+            out.push(BASE64URL[(bits >> (18 - 6 * position) & 0x3F) as usize] as char);
+        }
+    }
+    out
+}
+
+pub fn base64url_decode(text: &str) -> Result<Vec<u8>, ShareError> {
+    let text = text.trim().trim_end_matches('=');
+    let mut out = Vec::with_capacity(text.len() * 3 / 4);
+    for chunk in text.as_bytes().chunks(4) {
+        if chunk.len() == 1 {
+            return Err(ShareError::Truncated);
+        }
+        let mut bits = 0u32;
+        for (position, byte) in chunk.iter().enumerate() {
+            let value = BASE64URL
+                .iter()
+                .position(|c| c == byte)
+                .ok_or(ShareError::Base64)?;
+            bits |= (value as u32) << (18 - 6 * position);
+        }
+        out.extend_from_slice(&bits.to_be_bytes()[1..chunk.len()]);
+    }
+    Ok(out)
+}
+
+fn push_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn push_signed(out: &mut Vec<u8>, value: i64) {
+    push_varint(out, ((value << 1) ^ (value >> 63)) as u64);
+}
+
+fn push_hundredths(out: &mut Vec<u8>, value: f64) {
+    push_signed(out, (value * 100.0).round() as i64);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn byte(&mut self) -> Result<u8, ShareError> {
+        let (first, rest) = self.bytes.split_first().ok_or(ShareError::Truncated)?;
+        self.bytes = rest;
+        Ok(*first)
+    }
+
+    fn varint(&mut self) -> Result<u64, ShareError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= ((byte & 0x7F) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(ShareError::Truncated)
+    }
+
+    fn signed(&mut self) -> Result<i64, ShareError> {
+        let value = self.varint()?;
+        Ok((value >> 1) as i64 ^ -((value & 1) as i64))
+    }
+
+    fn hundredths(&mut self) -> Result<f64, ShareError> {
+        Ok(self.signed()? as f64 / 100.0)
+    }
+
+    fn name(&mut self) -> Result<String, ShareError> {
+        let length = self.varint()? as usize;
+        if length > self.bytes.len() {
+            return Err(ShareError::Truncated);
+        }
+        let (name, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+        String::from_utf8(name.to_vec()).map_err(|_| ShareError::BadName)
+    }
+}
+
+fn macro_fields(macros: &Macros) -> [Option<Grams>; 6] {
+    [
+        macros.fat,
+        macros.carbs,
+        macros.fiber,
+        macros.sugars,
+        macros.added_sugars,
+        macros.sugar_alcohols,
+    ]
+}
+
+pub fn to_bytes(items: &[GroceryItem], sort: SortOrder) -> Vec<u8> {
+    let mode = SortMode::ALL
+        .iter()
+        .position(|mode| *mode == sort.mode)
+        .unwrap_or(0);
+    let mut out = vec![SHARE_VERSION, mode as u8, sort.descending as u8];
+    push_varint(&mut out, items.len() as u64);
+    for item in items {
+        push_varint(&mut out, item.name.len() as u64);
+        out.extend_from_slice(item.name.as_bytes());
+        let optional: Vec<Option<Grams>> = std::iter::once(item.serving_grams)
+            .chain(macro_fields(&item.macros))
+            .collect();
+        let present = optional.iter().enumerate().fold(0u8, |bits, (bit, value)| {
+            bits | (value.is_some() as u8) << bit
+        });
+        out.push(present);
+        push_hundredths(&mut out, item.protein.value());
+        push_hundredths(&mut out, item.calories.value());
+        push_hundredths(&mut out, item.servings.value());
+        push_signed(&mut out, item.cost.cents());
+        for value in optional.into_iter().flatten() {
+            push_hundredths(&mut out, value.value());
+        }
+    }
+    out
+}
+
+pub fn from_bytes(bytes: &[u8]) -> Result<SharedList, ShareError> {
+    let mut reader = Reader { bytes };
+    let version = reader.byte()?;
+    if version != SHARE_VERSION {
+        return Err(ShareError::UnsupportedVersion(version));
+    }
+    let mode = reader.byte()?;
+    let sort = SortOrder {
+        mode: *SortMode::ALL
+            .get(mode as usize)
+            .ok_or(ShareError::UnknownSort(mode))?,
+        descending: reader.byte()? & 1 == 1,
+    };
+    let count = reader.varint()?;
+    /* Every item takes at least six bytes, so a huge count can only mean a damaged link. */
+    if count > reader.bytes.len() as u64 / 6 {
+        return Err(ShareError::Truncated);
+    }
+    let mut items = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let name = reader.name()?;
+        let present = reader.byte()?;
+        let protein = Grams::new(reader.hundredths()?);
+        let calories = Kcal::new(reader.hundredths()?);
+        let servings = Servings::new(reader.hundredths()?);
+        let cost = Money::from_cents(reader.signed()?);
+        let mut optional = [None::<Grams>; 7];
+        for (bit, value) in optional.iter_mut().enumerate() {
+            if present & (1 << bit) != 0 {
+                *value = Some(Grams::new(reader.hundredths()?));
+            }
+        }
+        let [
+            serving_grams,
+            fat,
+            carbs,
+            fiber,
+            sugars,
+            added_sugars,
+            sugar_alcohols,
+        ] = optional;
+        let mut item = GroceryItem {
+            protein,
+            calories,
+            cost,
+            servings,
+            name,
+            leanness: Default::default(),
+            ppd: Default::default(),
+            lpd: Default::default(),
+            serving_grams,
+            macros: Macros {
+                fat,
+                carbs,
+                fiber,
+                sugars,
+                added_sugars,
+                sugar_alcohols,
+            },
+            quality: None,
+            amino_acids: None,
+        };
+        /* A well-formed link can still carry values the form would refuse; don't load them. */
+        persist::check_item(&mut item).map_err(|message| ShareError::InvalidItem {
+            index: items.len(),
+            message,
+        })?;
+        items.push(item);
+    }
+    if !reader.bytes.is_empty() {
+        return Err(ShareError::TrailingBytes);
+    }
+    Ok(SharedList { items, sort })
+}
+
+pub fn encode(items: &[GroceryItem], sort: SortOrder) -> Result<String, ShareError> {
+    let code = base64url_encode(&to_bytes(items, sort));
+    if code.len() > MAX_LINK_CHARS {
+        return Err(ShareError::TooLarge {
+            length: code.len(),
+            limit: MAX_LINK_CHARS,
+        });
+    }
+    Ok(code)
+}
+
+pub fn decode(code: &str) -> Result<SharedList, ShareError> {
+    /* Checked before decoding so a pasted novel isn't parsed at all. */
+    if code.len() > MAX_LINK_CHARS {
+        return Err(ShareError::TooLarge {
+            length: code.len(),
+            limit: MAX_LINK_CHARS,
+        });
+    }
+    from_bytes(&base64url_decode(code)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eng::test_builder;
+
+    fn item(name: &str, protein: f64, servings: f64) -> GroceryItem {
+        /* Odd servings and a serving weight, so the rounding and optional fields are exercised. */
+        test_builder(name, protein, 165.0, 12.99)
+            .servings(Servings::new(servings))
+            .serving_grams(Some(Grams::new(112.0)))
+            .build()
+            .unwrap()
+    }
+
+    fn sort() -> SortOrder {
+        SortOrder {
+            mode: SortMode::Ppd,
+            descending: true,
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        let items = vec![item("Chicken", 31.0, 8.0), item("Crème fraîche", 2.5, 12.5)];
+        let shared = decode(&encode(&items, sort()).unwrap()).unwrap();
+        assert_eq!(shared.items, items);
+        assert_eq!(shared.sort, sort());
+    }
+
+    #[test]
+    fn rounds_to_hundredths() {
+        let items = vec![item("Chicken", 31.0, 28.125)];
+        let shared = decode(&encode(&items, sort()).unwrap()).unwrap();
+        assert_eq!(shared.items[0].servings, Servings::new(28.13));
+    }
+
+    #[test]
+    fn base64url_round_trips_every_length() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        for length in 0..8 {
+            let code = base64url_encode(&bytes[..length]);
+            assert_eq!(base64url_decode(&code), Ok(bytes[..length].to_vec()));
+        }
+    }
+
+    #[test]
+    fn rejects_truncated_links() {
+        let code = encode(&[item("Chicken", 31.0, 8.0)], sort()).unwrap();
+        for length in 1..code.len() {
+            let err = decode(&code[..length]).unwrap_err();
+            assert!(
+                matches!(err, ShareError::Truncated | ShareError::BadName),
+                "{} characters: {:?}",
+                length,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_versions_and_sorts() {
+        assert_eq!(
+            from_bytes(&[2, 0, 0, 0]),
+            Err(ShareError::UnsupportedVersion(2))
+        );
+        assert_eq!(
+            from_bytes(&[SHARE_VERSION, 200, 0, 0]),
+            Err(ShareError::UnknownSort(200))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_base64() {
+        assert_eq!(decode("AAAA+/"), Err(ShareError::Base64));
+        assert_eq!(decode("AQ AA"), Err(ShareError::Base64));
+    }
+
+    #[test]
+    fn rejects_trailing_bytes() {
+        let mut bytes = to_bytes(&[item("Chicken", 31.0, 8.0)], sort());
+        bytes.push(0);
+        assert_eq!(from_bytes(&bytes), Err(ShareError::TrailingBytes));
+    }
+
+    #[test]
+    fn rejects_items_the_form_would_refuse() {
+        let mut bad = item("Chicken", 31.0, 8.0);
+        bad.protein = Grams::new(-31.0);
+        let bytes = to_bytes(&[item("Eggs", 6.0, 12.0), bad], sort());
+        assert!(matches!(
+            from_bytes(&bytes),
+            Err(ShareError::InvalidItem { index: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn refuses_oversized_links() {
+        let items: Vec<GroceryItem> = (0..200)
+            .map(|number| item(&format!("Item number {}", number), 20.0, 8.0))
+            .collect();
+        assert!(matches!(
+            encode(&items, sort()),
+            Err(ShareError::TooLarge { .. })
+        ));
+        assert!(matches!(
+            decode(&"A".repeat(MAX_LINK_CHARS + 1)),
+            Err(ShareError::TooLarge { .. })
+        ));
+    }
+}
@@ -0,0 +1,448 @@
+use crate::eng::GroceryItem;
+use crate::persist::{self, PersistError};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+pub const KEY_PREFIX: &str = "protein-comparison-tool";
+
+/* The list being worked on, and where it goes if it can't be read back (`-2`, `-3`... once taken). */
+pub const CURRENT_LIST_KEY: &str = "protein-comparison-tool/list";
+pub const CURRENT_LIST_BACKUP_KEY: &str = "protein-comparison-tool/list-unreadable";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StorageError {
+    NotFound(String),
+    AlreadyExists(String),
+    InvalidName,
+    Backend(String),
+    Persist(PersistError),
+}
+
+impl Display for StorageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StorageError::NotFound(name) => write!(f, "There is no saved list called '{}'", name),
+            StorageError::AlreadyExists(name) => {
+                write!(f, "A list called '{}' already exists", name)
+            }
+            StorageError::InvalidName => write!(f, "Give the list a name"),
+            StorageError::Backend(message) => write!(f, "Storage failed: {}", message),
+            StorageError::Persist(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+impl From<PersistError> for StorageError {
+    fn from(err: PersistError) -> Self {
+        StorageError::Persist(err)
+    }
+}
+
+/* The smallest thing both the browser and tests can provide: string values under string keys. */
+pub trait KeyValueStore {
+    fn get(&self, key: &str) -> Option<String>;
+    fn set(&mut self, key: &str, value: &str) -> Result<(), StorageError>;
+    fn remove(&mut self, key: &str);
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MemoryStore(pub BTreeMap<String, String>);
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        MemoryStore::default()
+    }
+}
+
+impl KeyValueStore for MemoryStore {
+    fn get(&self, key: &str) -> Option<String> {
+        self.0.get(key).cloned()
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), StorageError> {
+        self.0.insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+
+    fn remove(&mut self, key: &str) {
+        self.0.remove(key);
+    }
+}
+
+/* Browser localStorage; `open()` is None outside a browser or when storage is disabled. */
+#[derive(Debug, Clone)]
+pub struct LocalStorage(web_sys::Storage);
+
+impl LocalStorage {
+    pub fn open() -> Option<Self> {
+        web_sys::window()?
+            .local_storage()
+            .ok()
+            .flatten()
+            .map(LocalStorage)
+    }
+}
+
+impl KeyValueStore for LocalStorage {
+    fn get(&self, key: &str) -> Option<String> {
+        self.0.get_item(key).ok().flatten()
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<(), StorageError> {
+        /* Usually the quota running out. */
+        self.0
+            .set_item(key, value)
+            .map_err(|err| StorageError::Backend(format!("{:?}", err)))
+    }
+
+    fn remove(&mut self, key: &str) {
+        _ = self.0.remove_item(key);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnreadableList {
+    pub error: PersistError,
+    pub backup_key: Option<String>, // None if even the backup couldn't be written
+}
+
+impl Display for UnreadableList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.backup_key {
+            Some(key) => write!(
+                f,
+                "Your saved list couldn't be read ({}); a copy was kept under '{}' in this browser's storage",
+                self.error, key
+            ),
+            None => write!(
+                f,
+                "Your saved list couldn't be read ({}) and no copy could be kept",
+                self.error
+            ),
+        }
+    }
+}
+
+impl std::error::Error for UnreadableList {}
+
+fn unused_backup_key<S: KeyValueStore>(store: &S, text: &str) -> String {
+    /* An earlier backup may be the only copy of a different list, so never write over it. */
+    std::iter::once(CURRENT_LIST_BACKUP_KEY.to_string())
+        .chain((2..).map(|n| format!("{}-{}", CURRENT_LIST_BACKUP_KEY, n)))
+        .find(|key| store.get(key).is_none_or(|kept| kept == text))
+        .unwrap_or_default()
+}
+
+pub fn load_current<S: KeyValueStore>(store: &mut S) -> Result<Vec<GroceryItem>, UnreadableList> {
+    /*
+    Nothing saved yet is an empty list. Something unreadable is copied to a backup key
+    first, so the empty list that replaces it doesn't destroy the only copy.
+    */
+    let Some(text) = store.get(CURRENT_LIST_KEY) else {
+        return Ok(vec![]);
+    };
+    persist::decode(&text).map_err(|error| {
+        let key = unused_backup_key(store, &text);
+        UnreadableList {
+            error,
+            backup_key: store.set(&key, &text).ok().map(|_| key),
+        }
+    })
+}
+
+/* Named lists on any backend: an index of names plus one versioned list per name. */
+#[derive(Debug, Clone)]
+pub struct SavedLists<S: KeyValueStore> {
+    store: S,
+}
+
+impl<S: KeyValueStore> SavedLists<S> {
+    pub fn new(store: S) -> Self {
+        SavedLists { store }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.store
+    }
+
+    fn index_key() -> String {
+        format!("{}/lists", KEY_PREFIX)
+    }
+
+    fn list_key(name: &str) -> String {
+        format!("{}/lists/{}", KEY_PREFIX, name)
+    }
+
+    fn clean_name(name: &str) -> Result<String, StorageError> {
+        let name = name.trim();
+        if name.is_empty() {
+            Err(StorageError::InvalidName)
+        } else {
+            Ok(name.to_string())
+        }
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        /* In the order they were first saved. An unreadable index reads as no lists. */
+        self.store
+            .get(&Self::index_key())
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    fn set_names(&mut self, names: &[String]) -> Result<(), StorageError> {
+        let text = serde_json::to_string(names).unwrap_or_default();
+        self.store.set(&Self::index_key(), &text)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.names().iter().any(|saved| saved == name.trim())
+    }
+
+    pub fn unused_name(&self, base: &str) -> String {
+        /* `base`, or `base 2`, `base 3` and so on, whichever is free first. */
+        let names = self.names();
+        std::iter::once(base.trim().to_string())
+            .chain((2..).map(|number| format!("{} {}", base.trim(), number)))
+            .find(|name| !names.contains(name))
+            .unwrap_or_default()
+    }
+
+    pub fn load(&self, name: &str) -> Result<Vec<GroceryItem>, StorageError> {
+        let name = Self::clean_name(name)?;
+        if !self.contains(&name) {
+            return Err(StorageError::NotFound(name));
+        }
+        let text = self
+            .store
+            .get(&Self::list_key(&name))
+            .ok_or(StorageError::NotFound(name))?;
+        Ok(persist::decode(&text)?)
+    }
+
+    pub fn save(&mut self, name: &str, items: &[GroceryItem]) -> Result<(), StorageError> {
+        /* Creates the list or overwrites it. */
+        let name = Self::clean_name(name)?;
+        self.store
+            .set(&Self::list_key(&name), &persist::encode(items))?;
+        let mut names = self.names();
+        if !names.contains(&name) {
+            names.push(name);
+            self.set_names(&names)?;
+        }
+        Ok(())
+    }
+
+    pub fn delete(&mut self, name: &str) -> Result<(), StorageError> {
+        let name = Self::clean_name(name)?;
+        let mut names = self.names();
+        if !names.contains(&name) {
+            return Err(StorageError::NotFound(name));
+        }
+        names.retain(|saved| *saved != name);
+        self.set_names(&names)?;
+        self.store.remove(&Self::list_key(&name));
+        Ok(())
+    }
+
+    pub fn duplicate(&mut self, from: &str, to: &str) -> Result<(), StorageError> {
+        let to = Self::clean_name(to)?;
+        if self.contains(&to) {
+            return Err(StorageError::AlreadyExists(to));
+        }
+        let items = self.load(from)?;
+        self.save(&to, &items)
+    }
+
+    pub fn rename(&mut self, from: &str, to: &str) -> Result<(), StorageError> {
+        /* Keeps the list's place in the index. */
+        let from = Self::clean_name(from)?;
+        let to = Self::clean_name(to)?;
+        if from == to {
+            return Ok(());
+        }
+        if self.contains(&to) {
+            return Err(StorageError::AlreadyExists(to));
+        }
+        let items = self.load(&from)?;
+        self.store
+            .set(&Self::list_key(&to), &persist::encode(&items))?;
+        let names: Vec<String> = self
+            .names()
+            .into_iter()
+            .map(|saved| if saved == from { to.clone() } else { saved })
+            .collect();
+        self.set_names(&names)?;
+        self.store.remove(&Self::list_key(&from));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eng::test_item;
+
+    fn chicken() -> GroceryItem {
+        test_item("Chicken", 31.0, 165.0, 12.99)
+    }
+
+    fn lists() -> SavedLists<MemoryStore> {
+        SavedLists::new(MemoryStore::new())
+    }
+
+    #[test]
+    fn saves_and_loads() {
+        let mut lists = lists();
+        lists.save(" Week 1 ", &[chicken()]).unwrap();
+        assert_eq!(lists.names(), vec!["Week 1".to_string()]);
+        assert!(lists.contains("Week 1"));
+        assert_eq!(lists.load("Week 1"), Ok(vec![chicken()]));
+        /* Saving again overwrites without a second index entry. */
+        lists.save("Week 1", &[]).unwrap();
+        assert_eq!(lists.names().len(), 1);
+        assert_eq!(lists.load("Week 1"), Ok(vec![]));
+    }
+
+    #[test]
+    fn deletes() {
+        let mut lists = lists();
+        lists.save("Week 1", &[chicken()]).unwrap();
+        lists.delete("Week 1").unwrap();
+        assert!(lists.names().is_empty());
+        assert_eq!(
+            lists.load("Week 1"),
+            Err(StorageError::NotFound("Week 1".to_string()))
+        );
+        assert_eq!(
+            lists.delete("Week 1"),
+            Err(StorageError::NotFound("Week 1".to_string()))
+        );
+        let key = SavedLists::<MemoryStore>::list_key("Week 1");
+        assert_eq!(lists.into_inner().get(&key), None);
+    }
+
+    #[test]
+    fn duplicates() {
+        let mut lists = lists();
+        lists.save("Week 1", &[chicken()]).unwrap();
+        lists.duplicate("Week 1", "Week 2").unwrap();
+        assert_eq!(
+            lists.names(),
+            vec!["Week 1".to_string(), "Week 2".to_string()]
+        );
+        assert_eq!(lists.load("Week 2"), Ok(vec![chicken()]));
+        assert_eq!(lists.load("Week 1"), Ok(vec![chicken()]));
+    }
+
+    #[test]
+    fn renames_in_place() {
+        let mut lists = lists();
+        lists.save("Week 1", &[chicken()]).unwrap();
+        lists.save("Week 2", &[]).unwrap();
+        lists.rename("Week 1", "Cutting").unwrap();
+        assert_eq!(
+            lists.names(),
+            vec!["Cutting".to_string(), "Week 2".to_string()]
+        );
+        assert_eq!(lists.load("Cutting"), Ok(vec![chicken()]));
+        assert_eq!(
+            lists.load("Week 1"),
+            Err(StorageError::NotFound("Week 1".to_string()))
+        );
+    }
+
+    #[test]
+    fn refuses_to_overwrite_on_duplicate_or_rename() {
+        let mut lists = lists();
+        lists.save("Week 1", &[chicken()]).unwrap();
+        lists.save("Week 2", &[]).unwrap();
+        let taken = Err(StorageError::AlreadyExists("Week 2".to_string()));
+        assert_eq!(lists.duplicate("Week 1", "Week 2"), taken);
+        assert_eq!(lists.rename("Week 1", "Week 2"), taken);
+        assert_eq!(lists.load("Week 2"), Ok(vec![]));
+    }
+
+    #[test]
+    fn refuses_empty_names() {
+        let mut lists = lists();
+        assert_eq!(lists.save("  ", &[]), Err(StorageError::InvalidName));
+        lists.save("Week 1", &[]).unwrap();
+        assert_eq!(lists.rename("Week 1", ""), Err(StorageError::InvalidName));
+        assert_eq!(
+            lists.duplicate("Week 1", ""),
+            Err(StorageError::InvalidName)
+        );
+        assert_eq!(lists.names(), vec!["Week 1".to_string()]);
+    }
+
+    #[test]
+    fn finds_an_unused_name() {
+        let mut lists = lists();
+        assert_eq!(
+            lists.unused_name("Before shared link"),
+            "Before shared link"
+        );
+        lists.save("Before shared link", &[]).unwrap();
+        lists.save("Before shared link 2", &[]).unwrap();
+        assert_eq!(
+            lists.unused_name("Before shared link"),
+            "Before shared link 3"
+        );
+    }
+
+    #[test]
+    fn current_list_starts_empty() {
+        let mut store = MemoryStore::new();
+        assert_eq!(load_current(&mut store), Ok(vec![]));
+        assert!(store.0.is_empty());
+    }
+
+    #[test]
+    fn current_list_loads() {
+        let mut store = MemoryStore::new();
+        store
+            .set(CURRENT_LIST_KEY, &persist::encode(&[chicken()]))
+            .unwrap();
+        assert_eq!(load_current(&mut store), Ok(vec![chicken()]));
+        assert_eq!(store.get(CURRENT_LIST_BACKUP_KEY), None);
+    }
+
+    #[test]
+    fn unreadable_current_list_is_backed_up() {
+        let mut store = MemoryStore::new();
+        store
+            .set(CURRENT_LIST_KEY, "{\"version\": 1, \"ite")
+            .unwrap();
+        let err = load_current(&mut store).unwrap_err();
+        assert!(matches!(err.error, PersistError::Json(_)));
+        assert_eq!(err.backup_key.as_deref(), Some(CURRENT_LIST_BACKUP_KEY));
+        assert_eq!(
+            store.get(CURRENT_LIST_BACKUP_KEY).as_deref(),
+            Some("{\"version\": 1, \"ite")
+        );
+    }
+
+    #[test]
+    fn an_earlier_backup_is_not_overwritten() {
+        let mut store = MemoryStore::new();
+        store.set(CURRENT_LIST_BACKUP_KEY, "older").unwrap();
+        store.set(CURRENT_LIST_KEY, "newer").unwrap();
+        let second = format!("{}-2", CURRENT_LIST_BACKUP_KEY);
+        assert_eq!(
+            load_current(&mut store).unwrap_err().backup_key,
+            Some(second.clone())
+        );
+        assert_eq!(store.get(CURRENT_LIST_BACKUP_KEY).as_deref(), Some("older"));
+        assert_eq!(store.get(&second).as_deref(), Some("newer"));
+
+        /* Reading the same bad value again reuses its backup rather than piling up copies. */
+        assert_eq!(
+            load_current(&mut store).unwrap_err().backup_key,
+            Some(second)
+        );
+        assert_eq!(store.0.len(), 3);
+    }
+}
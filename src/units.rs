@@ -0,0 +1,324 @@
+use crate::quantity::{Grams, Servings};
+use std::fmt::Display;
+use std::str::FromStr;
+
+pub const GRAMS_PER_OUNCE: f64 = 28.349523125;
+pub const GRAMS_PER_POUND: f64 = 453.59237;
+pub const GRAMS_PER_KILOGRAM: f64 = 1000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum WeightUnit {
+    #[default]
+    Gram,
+    Kilogram,
+    Ounce,
+    Pound,
+}
+
+impl WeightUnit {
+    pub const ALL: [WeightUnit; 4] = [
+        WeightUnit::Gram,
+        WeightUnit::Kilogram,
+        WeightUnit::Ounce,
+        WeightUnit::Pound,
+    ];
+
+    pub fn grams_per_unit(&self) -> f64 {
+        match self {
+            WeightUnit::Gram => 1.0,
+            WeightUnit::Kilogram => GRAMS_PER_KILOGRAM,
+            WeightUnit::Ounce => GRAMS_PER_OUNCE,
+            WeightUnit::Pound => GRAMS_PER_POUND,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ServingUnit {
+    #[default]
+    Gram,
+    Milliliter,
+    Scoop,
+}
+
+impl ServingUnit {
+    pub const ALL: [ServingUnit; 3] = [
+        ServingUnit::Gram,
+        ServingUnit::Milliliter,
+        ServingUnit::Scoop,
+    ];
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnitError {
+    NonPositive(&'static str),
+    MissingDensity,
+    MissingScoopWeight,
+    UnknownUnit(String),
+}
+
+impl Display for UnitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UnitError::NonPositive(what) => write!(f, "The {} must be greater than zero", what),
+            UnitError::MissingDensity => write!(f, "Enter a density (g/ml) to use millilitres"),
+            UnitError::MissingScoopWeight => write!(f, "Enter the scoop weight (g) to use scoops"),
+            UnitError::UnknownUnit(unit) => write!(f, "'{}' is not a known unit", unit),
+        }
+    }
+}
+
+impl std::error::Error for UnitError {}
+
+impl Display for WeightUnit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            WeightUnit::Gram => "g",
+            WeightUnit::Kilogram => "kg",
+            WeightUnit::Ounce => "oz",
+            WeightUnit::Pound => "lb",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+impl FromStr for WeightUnit {
+    type Err = UnitError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "g" | "gram" | "grams" => Ok(WeightUnit::Gram),
+            "kg" | "kilogram" | "kilograms" => Ok(WeightUnit::Kilogram),
+            "oz" | "ounce" | "ounces" => Ok(WeightUnit::Ounce),
+            "lb" | "lbs" | "pound" | "pounds" => Ok(WeightUnit::Pound),
+            other => Err(UnitError::UnknownUnit(other.to_string())),
+        }
+    }
+}
+
+impl Display for ServingUnit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            ServingUnit::Gram => "g",
+            ServingUnit::Milliliter => "ml",
+            ServingUnit::Scoop => "scoop",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+impl FromStr for ServingUnit {
+    type Err = UnitError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "g" | "gram" | "grams" => Ok(ServingUnit::Gram),
+            "ml" | "milliliter" | "milliliters" | "millilitre" | "millilitres" => {
+                Ok(ServingUnit::Milliliter)
+            }
+            "scoop" | "scoops" => Ok(ServingUnit::Scoop),
+            other => Err(UnitError::UnknownUnit(other.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Weight {
+    pub amount: f64,
+    pub unit: WeightUnit,
+}
+
+impl Weight {
+    pub fn new(amount: f64, unit: WeightUnit) -> Self {
+        Weight { amount, unit }
+    }
+
+    pub fn to_grams(&self) -> Grams {
+        Grams::new(self.amount * self.unit.grams_per_unit())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ServingSize {
+    pub amount: f64,
+    pub unit: ServingUnit,
+}
+
+/* How to turn volume and scoops into grams; only needed for those units. */
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Conversion {
+    pub density_g_per_ml: Option<f64>,
+    pub scoop_grams: Option<f64>,
+}
+
+impl ServingSize {
+    pub fn new(amount: f64, unit: ServingUnit) -> Self {
+        ServingSize { amount, unit }
+    }
+
+    pub fn to_grams(&self, conversion: &Conversion) -> Result<Grams, UnitError> {
+        let grams_per_unit = match self.unit {
+            ServingUnit::Gram => 1.0,
+            ServingUnit::Milliliter => conversion
+                .density_g_per_ml
+                .ok_or(UnitError::MissingDensity)?,
+            ServingUnit::Scoop => conversion
+                .scoop_grams
+                .ok_or(UnitError::MissingScoopWeight)?,
+        };
+        if grams_per_unit.is_nan() || grams_per_unit <= 0.0 {
+            return Err(match self.unit {
+                ServingUnit::Milliliter => UnitError::NonPositive("density"),
+                _ => UnitError::NonPositive("scoop weight"),
+            });
+        }
+        Ok(Grams::new(self.amount * grams_per_unit))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Package {
+    pub weight: Weight,
+    pub serving: ServingSize,
+    pub conversion: Conversion,
+}
+
+impl Package {
+    pub fn servings(&self) -> Result<Servings, UnitError> {
+        /* A 2 lb tub and a 900 g tub both reduce to servings of the labelled size. */
+        let package_grams = self.weight.to_grams().value();
+        if package_grams.is_nan() || package_grams <= 0.0 {
+            return Err(UnitError::NonPositive("package weight"));
+        }
+        let serving_grams = self.serving.to_grams(&self.conversion)?.value();
+        if serving_grams.is_nan() || serving_grams <= 0.0 {
+            return Err(UnitError::NonPositive("serving size"));
+        }
+        Ok(Servings::new(package_grams / serving_grams))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn converts_weights_to_grams() {
+        assert!(close(
+            Weight::new(1.0, WeightUnit::Ounce).to_grams().value(),
+            28.349523125
+        ));
+        assert!(close(
+            Weight::new(2.0, WeightUnit::Pound).to_grams().value(),
+            907.18474
+        ));
+        assert!(close(
+            Weight::new(0.9, WeightUnit::Kilogram).to_grams().value(),
+            900.0
+        ));
+        assert!(close(
+            Weight::new(900.0, WeightUnit::Gram).to_grams().value(),
+            900.0
+        ));
+    }
+
+    #[test]
+    fn weights_round_trip_through_grams() {
+        for unit in [WeightUnit::Ounce, WeightUnit::Pound, WeightUnit::Kilogram] {
+            let grams = Weight::new(12.5, unit).to_grams().value();
+            assert!(close(grams / unit.grams_per_unit(), 12.5));
+        }
+        /* 16 oz is exactly a pound. */
+        assert!(close(
+            Weight::new(16.0, WeightUnit::Ounce).to_grams().value(),
+            Weight::new(1.0, WeightUnit::Pound).to_grams().value()
+        ));
+    }
+
+    #[test]
+    fn parses_units_forgivingly() {
+        assert_eq!(" LBS ".parse::<WeightUnit>(), Ok(WeightUnit::Pound));
+        assert_eq!("ounces".parse::<WeightUnit>(), Ok(WeightUnit::Ounce));
+        assert_eq!(
+            "Millilitres".parse::<ServingUnit>(),
+            Ok(ServingUnit::Milliliter)
+        );
+        assert_eq!("scoops".parse::<ServingUnit>(), Ok(ServingUnit::Scoop));
+    }
+
+    #[test]
+    fn labels_parse_back() {
+        for unit in WeightUnit::ALL {
+            assert_eq!(unit.to_string().parse::<WeightUnit>(), Ok(unit));
+        }
+        for unit in ServingUnit::ALL {
+            assert_eq!(unit.to_string().parse::<ServingUnit>(), Ok(unit));
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_units() {
+        assert_eq!(
+            "stone".parse::<WeightUnit>(),
+            Err(UnitError::UnknownUnit("stone".to_string()))
+        );
+        assert_eq!(
+            "cup".parse::<ServingUnit>(),
+            Err(UnitError::UnknownUnit("cup".to_string()))
+        );
+    }
+
+    #[test]
+    fn servings_need_a_conversion_for_volume_and_scoops() {
+        let conversion = Conversion::default();
+        assert_eq!(
+            ServingSize::new(250.0, ServingUnit::Milliliter).to_grams(&conversion),
+            Err(UnitError::MissingDensity)
+        );
+        assert_eq!(
+            ServingSize::new(1.0, ServingUnit::Scoop).to_grams(&conversion),
+            Err(UnitError::MissingScoopWeight)
+        );
+        let conversion = Conversion {
+            density_g_per_ml: Some(1.03),
+            scoop_grams: Some(0.0),
+        };
+        assert!(close(
+            ServingSize::new(250.0, ServingUnit::Milliliter)
+                .to_grams(&conversion)
+                .unwrap()
+                .value(),
+            257.5
+        ));
+        assert_eq!(
+            ServingSize::new(1.0, ServingUnit::Scoop).to_grams(&conversion),
+            Err(UnitError::NonPositive("scoop weight"))
+        );
+    }
+
+    #[test]
+    fn a_two_pound_tub_of_scoops() {
+        let package = Package {
+            weight: Weight::new(2.0, WeightUnit::Pound),
+            serving: ServingSize::new(1.0, ServingUnit::Scoop),
+            conversion: Conversion {
+                density_g_per_ml: None,
+                scoop_grams: Some(30.0),
+            },
+        };
+        assert!(close(package.servings().unwrap().value(), 907.18474 / 30.0));
+
+        let empty = Package {
+            weight: Weight::new(0.0, WeightUnit::Pound),
+            ..package
+        };
+        assert_eq!(
+            empty.servings(),
+            Err(UnitError::NonPositive("package weight"))
+        );
+    }
+}